@@ -41,6 +41,31 @@
   html.tag("unique", content)
 }
 
+/// Creates a permalink element in HTML head.
+/// The permalink overrides the path-based slug of the article,
+/// so that the URL won't change after moving the file.
+///
+// `~>` means that will be processed by typsite
+///
+/// - slug (str):
+///    The public slug of the article, e.g. "/blog/hello".
+/// -> permalink element ~> nothing, the slug is recorded in metadata
+#let permalink(slug) = {
+  html.tag("permalink", slug: str(slug))[]
+}
+
+/// Creates an alias element in HTML head.
+/// Other articles could refer to the article by its aliases.
+///
+// `~>` means that will be processed by typsite
+///
+/// - slug (str):
+///    An alternative slug of the article.
+/// -> alias element ~> nothing, the slug is recorded in metadata
+#let alias(slug) = {
+  html.tag("alias", slug: str(slug))[]
+}
+
 // Rewrites
 // `~>` means that will be processed by typsite
 
@@ -95,12 +120,19 @@
 /// - heading-level (str): "child" | "peer" | exact heading level(1-6) or using heading like `== #embed("./example.typ")`
 ///     The level of the heading for the embed, which can be "child", "peer", or an exact heading level (1-6).
 ///     Or i.e., you can use a heading like `== #embed("./example.typ")` to set the heading level as `2`.
+/// - section (str, none):
+///     Only embed a section of the article, by its heading numbering (e.g. "2.1") or heading anchor.
+///     The section is numbered as the embedded article, its title is used as the embed title.
+/// - template (str, none):
+///     The embed template to render with, by its name in `.typsite/components/embeds/` (e.g. "card"),
+///     or the default `.typsite/components/embed.html` if none.
 /// - attrs (..):
 ///     Additional attributes to be applied to the embed element.
 ///     Values will be converted to string using `to-str` function.
 ///     You can use these attributes by `{key}` in the embed component config (.typsite/components/embed.html)
+///     Each attribute must be declared by the embedded article (see `embed-variable`) or the embed component.
 /// -> embed component ~> HTML embed article  (not a `<embed>` tag)
-#let embed(slug, open: true, sidebar: "full", heading-level: "child", ..attrs) = context {
+#let embed(slug, open: true, sidebar: "full", heading-level: "child", section: none, template: none, ..attrs) = context {
   if target() != "html" {
     return pdf.embed(slug)
   }
@@ -129,10 +161,36 @@
     open: to-str(open),
     sidebar: sidebar,
     heading_level: str(heading-level),
+    ..if section != none { (section: str(section)) } else { (:) },
+    ..if template != none { (template: str(template)) } else { (:) },
     ..attrs,
   )[]
 }
 
+/// Declares a variable accepted when the article is embedded.
+///   The variable could be used by `{name}` in the embed component and the article.
+///
+/// - name (str):
+///     The name of the variable, passed as an additional attribute of `embed`.
+/// - type (str): "string" | "bool" | "number"
+///     The type of the variable, values of other types are rejected.
+/// - default (str, bool, int, float, none):
+///     The default value of the variable, the variable is required if none.
+/// -> embed-variable tag ~> none
+#let embed-variable(name, type: "string", default: none) = context {
+  if type != "string" and type != "bool" and type != "number" {
+    panic("Expect 'string' | 'bool' | 'number' of #embed-variable.type, but got: " + type)
+  }
+  if target() == "html" {
+    html.tag(
+      "embedvariable",
+      name: str(name),
+      type: type,
+      ..if default != none { (default: to-str(default)) } else { (:) },
+    )[]
+  }
+}
+
 
 // MetaContent
 
@@ -281,6 +339,17 @@
   metaoption("sidebar", type)
 }
 
+
+/// Set pdf metaoption.
+///
+/// - enable (bool):
+///    Whether to export the article as PDF next to its page,
+///    which overrides `pdf.enable` in options.toml.
+/// -> set-metaoption pdf tag ~> none
+#let pdf-export(enable: true) = {
+  metaoption("pdf", if enable { "true" } else { "false" })
+}
+
 // MetaGraph
 
 /// Set metagraph
@@ -310,6 +379,16 @@
 /// -> set-metagraph parent tag ~> none
 #let parent(slug) = metagraph("parent", slug: slug)
 
+/// Set custom relation metagraph
+///  The relation must be declared in `[[relations]]` of `options.toml`, by its name or inverse.
+///
+/// - kind (str):
+///    The name of the relation, e.g. "prerequisite".
+/// - slug (str):
+///    The slug of the related article.
+/// -> set-metagraph relation tag ~> none
+#let relation(kind, slug) = metagraph(kind, slug: slug)
+
 
 // Other
 
@@ -0,0 +1,74 @@
+// Client of the local graph data generated by typsite next to each page.
+// Nodes are placed on rings by their hops from the center article.
+(() => {
+  const SIZE = 280;
+  const RADIUS = 6;
+  const SVG_NS = "http://www.w3.org/2000/svg";
+
+  const element = (name, attrs) => {
+    const el = document.createElementNS(SVG_NS, name);
+    Object.entries(attrs).forEach(([key, value]) => el.setAttribute(key, value));
+    return el;
+  };
+
+  const layout = (graph) => {
+    const rings = new Map();
+    graph.nodes.forEach((node) => {
+      if (!rings.has(node.hops)) rings.set(node.hops, []);
+      rings.get(node.hops).push(node);
+    });
+    const depth = Math.max(...rings.keys(), 1);
+    const positions = new Map();
+    rings.forEach((nodes, hops) => {
+      const radius = (hops / depth) * (SIZE / 2 - 40);
+      nodes.forEach((node, i) => {
+        const angle = (2 * Math.PI * i) / nodes.length + hops;
+        positions.set(node.slug, {
+          x: SIZE / 2 + radius * Math.cos(angle),
+          y: SIZE / 2 + radius * Math.sin(angle),
+        });
+      });
+    });
+    return positions;
+  };
+
+  const render = (container, graph) => {
+    const positions = layout(graph);
+    const svg = element("svg", { viewBox: `0 0 ${SIZE} ${SIZE}`, role: "img" });
+    graph.edges.forEach((edge) => {
+      const from = positions.get(edge.source);
+      const to = positions.get(edge.target);
+      if (!from || !to) return;
+      svg.appendChild(element("line", {
+        x1: from.x, y1: from.y, x2: to.x, y2: to.y,
+        class: `localgraph-edge ${edge.kind}`,
+      }));
+    });
+    graph.nodes.forEach((node) => {
+      const { x, y } = positions.get(node.slug);
+      const link = element("a", { href: node.url });
+      const title = element("title", {});
+      title.textContent = node.title || node.slug;
+      const circle = element("circle", {
+        cx: x, cy: y, r: node.slug === graph.center ? RADIUS * 1.5 : RADIUS,
+        class: node.slug === graph.center ? "localgraph-node center" : "localgraph-node",
+      });
+      const label = element("text", { x, y: y + RADIUS * 2.8, class: "localgraph-label" });
+      label.textContent = node.title || node.slug;
+      link.append(title, circle, label);
+      svg.appendChild(link);
+    });
+    container.appendChild(svg);
+  };
+
+  document.querySelectorAll(".localgraph").forEach((container) => {
+    const src = container.dataset.src;
+    if (!src) return;
+    fetch(src)
+      .then((response) => response.json())
+      .then((graph) => {
+        if (graph.nodes.length > 1) render(container, graph);
+      })
+      .catch(() => {});
+  });
+})();
@@ -0,0 +1,128 @@
+// Client of the static search index generated by typsite.
+// Tokenizing and sharding must stay the same as `src/compile/compiler/search.rs`.
+(() => {
+  const MAX_RESULTS = 10;
+
+  const isCjk = (c) => {
+    const code = c.codePointAt(0);
+    return (code >= 0x3040 && code <= 0x30ff) ||
+      (code >= 0x3400 && code <= 0x4dbf) ||
+      (code >= 0x4e00 && code <= 0x9fff) ||
+      (code >= 0xac00 && code <= 0xd7af) ||
+      (code >= 0xf900 && code <= 0xfaff) ||
+      (code >= 0x20000 && code <= 0x2fa1f);
+  };
+
+  const tokenize = (text) => {
+    const tokens = [];
+    let word = "";
+    let run = [];
+    const flushWord = () => {
+      if (word) tokens.push(word);
+      word = "";
+    };
+    const flushCjk = () => {
+      run.forEach((c) => tokens.push(c));
+      for (let i = 0; i + 1 < run.length; i++) tokens.push(run[i] + run[i + 1]);
+      run = [];
+    };
+    for (const c of text.toLowerCase()) {
+      if (isCjk(c)) {
+        flushWord();
+        run.push(c);
+      } else if (/[\p{L}\p{N}]/u.test(c)) {
+        flushCjk();
+        word += c;
+      } else {
+        flushCjk();
+        flushWord();
+      }
+    }
+    flushCjk();
+    flushWord();
+    return tokens;
+  };
+
+  // FNV-1a over the UTF-8 bytes
+  const shardOf = (token, shards) => {
+    let hash = 0x811c9dc5;
+    for (const byte of new TextEncoder().encode(token)) {
+      hash ^= byte;
+      hash = Math.imul(hash, 0x01000193) >>> 0;
+    }
+    return hash % shards;
+  };
+
+  const cache = new Map();
+  const fetchJson = (url) => {
+    if (!cache.has(url)) {
+      cache.set(url, fetch(url).then((res) => (res.ok ? res.json() : null)));
+    }
+    return cache.get(url);
+  };
+
+  const search = async (base, query) => {
+    const tokens = [...new Set(tokenize(query))];
+    if (tokens.length === 0) return [];
+    const meta = await fetchJson(`${base}meta.json`);
+    const docs = await fetchJson(`${base}docs.json`);
+    if (!meta || !docs) return [];
+    // Every token must be matched
+    let scores = null;
+    for (const token of tokens) {
+      const shard = await fetchJson(`${base}index-${shardOf(token, meta.shards)}.json`);
+      const postings = (shard && shard[token]) || {};
+      const next = new Map();
+      for (const [id, tf] of Object.entries(postings)) {
+        if (scores === null || scores.has(id)) next.set(id, (scores ? scores.get(id) : 0) + tf);
+      }
+      scores = next;
+      if (scores.size === 0) break;
+    }
+    return [...scores.entries()]
+      .sort((a, b) => b[1] - a[1])
+      .slice(0, MAX_RESULTS)
+      .map(([id]) => docs[id]);
+  };
+
+  const render = (list, docs) => {
+    list.replaceChildren(
+      ...docs.map((doc) => {
+        const item = document.createElement("li");
+        const link = document.createElement("a");
+        link.href = doc.url;
+        if (doc.taxon) {
+          const taxon = document.createElement("span");
+          taxon.className = "search-taxon";
+          taxon.textContent = doc.taxon;
+          link.append(taxon);
+        }
+        link.append(doc.title || doc.url);
+        const summary = document.createElement("span");
+        summary.className = "search-summary";
+        summary.textContent = doc.summary;
+        link.append(summary);
+        item.append(link);
+        return item;
+      }),
+    );
+  };
+
+  document.querySelectorAll(".search").forEach((container) => {
+    const input = container.querySelector(".search-input");
+    const list = container.querySelector(".search-results");
+    const base = container.dataset.index || "/search/";
+    let latest = 0;
+    input.addEventListener("input", async () => {
+      const current = ++latest;
+      const docs = await search(base, input.value);
+      if (current === latest) render(list, docs);
+    });
+    input.addEventListener("keydown", (event) => {
+      if (event.key === "Escape") {
+        input.value = "";
+        list.replaceChildren();
+      }
+    });
+  });
+})();
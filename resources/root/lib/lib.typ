@@ -2,6 +2,7 @@
 #import "@local/typsite:0.1.0": (
   inline,
   embed,
+  embed-variable,
   metacontent,
   get-metacontent,
   title,
@@ -11,8 +12,11 @@
   date,
   heading-numbering,
   sidebar,
+  pdf-export,
   parent,
-  unique
+  unique,
+  permalink,
+  alias,
 )
 
 #import "html.typ" as html
@@ -27,7 +31,7 @@
 )
 
 #import "rewrite.typ" :  (
-  cite, cite-title
+  cite, cite-title, callout, graphviz
 )
 
 
@@ -27,19 +27,90 @@
 ///     The programming language of the code snippet. 
 /// - theme (str): 
 ///     The theme to be applied to the code snippet. 
+/// - line-numbers (bool):
+///     Whether to show line numbers.
+/// - start (int):
+///     The number of the first line.
+/// - highlight (str, none):
+///     The line ranges to highlight by line number, like "1,3-5".
+/// - title (str, none):
+///     The file name shown as the caption of the code block.
+/// - diff (bool):
+///     Whether to mark lines starting with `+` / `-` as added / deleted.
 /// - content (content): 
 ///     The content of the code snippet. 
 /// -> rewrite ~> HTML code block element
-#let code-block(lang: "text", theme: "onedark", content) = {
+#let code-block(
+  lang: "text",
+  theme: "onedark",
+  line-numbers: false,
+  start: 1,
+  highlight: none,
+  title: none,
+  diff: false,
+  content,
+) = {
   rewrite(
     "code-block",
     lang: str(lang),
     theme: str(theme),
+    line-numbers: to-str(line-numbers),
+    start: str(start),
+    ..if highlight != none { (highlight: str(highlight)) } else { (:) },
+    ..if title != none { (title: str(title)) } else { (:) },
+    diff: to-str(diff),
     content: to-str(content),
     content,
   )
 }
 
+/// Creates a code block rewrite in HTML from a source file.
+///
+/// - src (str):
+///     The path of the source file, relative to the current file, or to the root if starting with `/`.
+/// - lang (str, none):
+///     The programming language of the code, inferred from the file extension if none.
+/// - lines (str, none):
+///     The line range to include, like "10-20".
+/// - region (str, none):
+///     The region to include, between `region: <name>` and `endregion` marker comments.
+/// - theme (str):
+///     The theme to be applied to the code.
+/// - line-numbers (bool):
+///     Whether to show line numbers.
+/// - start (int, none):
+///     The number of the first line, the first line of the range if none.
+/// - highlight (str, none):
+///     The line ranges to highlight by line number, like "1,3-5".
+/// - title (str, none):
+///     The file name shown as the caption of the code block.
+/// -> rewrite ~> HTML code block element
+#let code-file(
+  src,
+  lang: none,
+  lines: none,
+  region: none,
+  theme: "onedark",
+  line-numbers: false,
+  start: none,
+  highlight: none,
+  title: none,
+) = {
+  rewrite(
+    "code-block",
+    src: str(src),
+    ..if lang != none { (lang: str(lang)) } else { (:) },
+    ..if lines != none { (lines: str(lines)) } else { (:) },
+    ..if region != none { (region: str(region)) } else { (:) },
+    theme: str(theme),
+    line-numbers: to-str(line-numbers),
+    ..if start != none { (start: str(start)) } else { (:) },
+    ..if highlight != none { (highlight: str(highlight)) } else { (:) },
+    ..if title != none { (title: str(title)) } else { (:) },
+    raw(src),
+  )
+}
+
 /// Creates a footnote definition rewrite in HTML. 
 ///
 /// - name (str):
@@ -87,4 +158,37 @@
   } else {
     content
   }
-}
\ No newline at end of file
+}
+
+/// Creates a callout rewrite in HTML, rendered by the `template` pass.
+///
+/// - title (str):
+///     The title of the callout.
+/// - kind (str): "note" | "warning" | "danger"
+///     The kind of the callout.
+/// - content (content):
+///     The content of the callout.
+/// -> rewrite ~> HTML callout element
+#let callout(title, kind: "note", content) = context {
+  if target() == "html" {
+    rewrite("callout", title: str(title), kind: str(kind), content)
+  } else {
+    block[*#title* \ #content]
+  }
+}
+
+
+/// Creates a Graphviz diagram rewrite in HTML, rendered by the `process` pass.
+///   Requires `dot` of Graphviz to be installed.
+///
+/// - code (str, raw):
+///     The DOT source of the diagram.
+/// -> rewrite ~> HTML svg diagram
+#let graphviz(code) = context {
+  let code = if type(code) == str { code } else { code.text }
+  if target() == "html" {
+    rewrite("graphviz", content: code)[]
+  } else {
+    raw(code, lang: "dot", block: true)
+  }
+}
@@ -1,7 +1,8 @@
 use std::{env, fs, process::exit};
 
-use crate::compile::compiler::clean_dir;
+use crate::compile::compiler::{GraphFilter, clean_dir};
 use crate::compile::options::CompileOptions;
+use crate::compile::options::graph::{EdgeKind, GraphFormat, default_edges};
 use crate::config::highlight::CodeHightlightConfig;
 use crate::resource::default::copy_default_typsite;
 use crate::resource::package::install_included_packages;
@@ -29,6 +30,9 @@ impl Executor {
             Command::Compile(compile_cmd) => Self::execute_compile(compile_cmd).await,
             Command::Clean(clean_cmd) => Self::execute_clean(clean_cmd),
             Command::Syntect(syntect_cmd) => Self::execute_syntect(syntect_cmd),
+            Command::Export(export_cmd) => Self::execute_export(export_cmd),
+            Command::Graph(graph_cmd) => Self::execute_graph(graph_cmd),
+            Command::Check(check_cmd) => Self::execute_check(check_cmd),
         }
     }
 
@@ -84,6 +88,7 @@ impl Executor {
             watch: cmd.port != 0,
             short_slug: !cmd.no_short_slug,
             pretty_url: !cmd.no_pretty_url,
+            cache_path: cache_path.clone(),
         };
         let compiler = Compiler::new(
             config,
@@ -130,6 +135,60 @@ impl Executor {
         Ok(())
     }
 
+    fn execute_export(export_cmd: ExportCmd) -> Result<()> {
+        match export_cmd.format {
+            ExportFormat::Epub(epub_cmd) => {
+                let cwd = env::current_dir().context("Failed to get current work dir")?;
+                let file = verify_if_relative_path(&cwd, epub_cmd.file.as_str())?;
+                let compiler = Self::build_compiler(epub_cmd.compile)?;
+                install_included_packages()?;
+                compiler.export_epub(&epub_cmd.root, &file)?;
+                println!("Exporting done.");
+            }
+        }
+        Ok(())
+    }
+
+    fn execute_graph(graph_cmd: GraphCmd) -> Result<()> {
+        let cwd = env::current_dir().context("Failed to get current work dir")?;
+        let file = graph_cmd.file.unwrap_or_else(|| match graph_cmd.format {
+            GraphFormat::Json => "./graph.json".to_string(),
+            GraphFormat::Dot => "./graph.dot".to_string(),
+        });
+        let file = verify_if_relative_path(&cwd, file.as_str())?;
+        let edges = if graph_cmd.edges.is_empty() {
+            default_edges()
+        } else {
+            graph_cmd.edges
+        };
+        let filter = GraphFilter {
+            prefix: graph_cmd.prefix.as_deref(),
+            edges: &edges,
+        };
+        let compiler = Self::build_compiler(graph_cmd.compile)?;
+        install_included_packages()?;
+        compiler.export_graph(&filter, graph_cmd.format, &file)?;
+        println!("Exporting done.");
+        Ok(())
+    }
+
+    fn execute_check(check_cmd: CheckCmd) -> Result<()> {
+        if !check_cmd.orphans {
+            println!("Nothing to check, try `--orphans`");
+            return Ok(());
+        }
+        let compiler = Self::build_compiler(check_cmd.compile)?;
+        install_included_packages()?;
+        let report = compiler.check_orphans()?;
+        if report.is_empty() {
+            println!("No orphan articles found.");
+        } else {
+            print!("{report}");
+            exit(1);
+        }
+        Ok(())
+    }
+
     fn execute_syntect(syntect_cmd: SyntectCmd) -> Result<()> {
         let config_path = Path::new(&syntect_cmd.config);
         let config = CodeHightlightConfig::load(config_path);
@@ -154,6 +213,15 @@ enum Command {
     /// Check syntect syntaxes & themes
     #[command(visible_alias = "s")]
     Syntect(SyntectCmd),
+
+    /// Export an article tree into other formats.
+    Export(ExportCmd),
+
+    /// Dump all articles and their graph edges as JSON or Graphviz DOT.
+    Graph(GraphCmd),
+
+    /// Check the articles of the project.
+    Check(CheckCmd),
 }
 
 #[derive(clap::Args)]
@@ -203,6 +271,65 @@ struct CompileCmd {
     #[arg(long, default_value_t = false)]
     no_short_slug: bool,
 }
+#[derive(clap::Args)]
+struct ExportCmd {
+    #[command(subcommand)]
+    format: ExportFormat,
+}
+
+#[derive(clap::Subcommand)]
+enum ExportFormat {
+    /// Export the article tree from the root as an EPUB 3 book.
+    Epub(EpubCmd),
+}
+
+#[derive(clap::Args)]
+struct EpubCmd {
+    /// Slug of the root article.
+    #[arg(long)]
+    root: String,
+
+    /// EPUB file path.
+    #[arg(short, long, default_value_t = format!("./book.epub"))]
+    file: String,
+
+    #[command(flatten)]
+    compile: CompileCmd,
+}
+
+#[derive(clap::Args)]
+struct GraphCmd {
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = GraphFormat::Json)]
+    format: GraphFormat,
+
+    /// Graph file path, `./graph.json` or `./graph.dot` by default.
+    #[arg(short, long)]
+    file: Option<String>,
+
+    /// Only articles whose slug starts with the prefix.
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// Edge kinds to keep, all by default.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    edges: Vec<EdgeKind>,
+
+    #[command(flatten)]
+    compile: CompileCmd,
+}
+
+#[derive(clap::Args)]
+struct CheckCmd {
+    /// List articles without parent, backlinks, nor embedded anywhere,
+    /// and the ones whose parent chain doesn't lead to the default parent.
+    #[arg(long, default_value_t = false)]
+    orphans: bool,
+
+    #[command(flatten)]
+    compile: CompileCmd,
+}
+
 #[derive(clap::Args)]
 struct SyntectCmd {
     /// Project config path
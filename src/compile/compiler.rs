@@ -1,16 +1,29 @@
 use crate::compile::compiler::cache::article::ArticleCache;
 use crate::compile::compiler::cache::dep::RevDeps;
 use crate::compile::options::CompileOptions;
-use crate::compile::registry::KeyRegistry;
+use crate::compile::options::graph::GraphFormat;
+use crate::compile::registry::{Key, KeyRegistry};
 use crate::config::TypsiteConfig;
-use crate::util::fs::remove_dir_all;
+use crate::ir::article::Article;
+use crate::pass::rewrite::clean_highlight_cache;
+use crate::util::fs::{remove_dir_all, write_into_file};
 use crate::util::html::OutputHtml;
 use analysis::*;
+use code_theme::{generate_code_themes, stale_code_themes};
+use feed::generate_feeds;
+use graph::{LocalGraphInput, generate_graph, generate_local_graphs};
+pub use graph::{GraphFilter, SiteGraph};
+use search::{SearchInput, generate_search_index, stale_search_shards};
+use sitemap::{generate_sitemap, stale_sitemap_shards};
+use social_card::{SocialCardInput, generate_social_cards};
 use anyhow::*;
 use html_pass::pass_html;
 use initializer::{Input, initialize};
 use output_sync::{Output, sync_files_to_output};
+pub use orphans::OrphanReport;
+use epub::{EpubInput, export_epub};
 use page_composer::{PageData, compose_pages};
+use pdf::{PdfData, PdfInput, export_pdfs};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::result::Result::Ok;
@@ -21,10 +34,20 @@ use super::watch::watch;
 use super::{init_compile_options, proj_options};
 
 mod analysis;
+mod code_theme;
+mod epub;
+mod feed;
+mod graph;
 mod html_pass;
 mod initializer;
+mod orphans;
 mod output_sync;
 mod page_composer;
+mod pdf;
+mod print;
+mod search;
+mod sitemap;
+mod social_card;
 mod typst_pass;
 
 mod cache {
@@ -35,7 +58,12 @@ mod cache {
 
 type PathBufs = HashSet<PathBuf>;
 type ErrorArticles = Vec<(PathBuf, String)>;
-type UpdatedPages<'a> = Vec<(Arc<Path>, OutputHtml<'a>)>;
+// (output path, content)
+type GeneratedFiles = Vec<(PathBuf, String)>;
+// (source path, output path)
+type CopiedFiles = Vec<(PathBuf, PathBuf)>;
+// (typst path, output page path, html)
+type UpdatedPages<'a> = Vec<(Arc<Path>, PathBuf, OutputHtml<'a>)>;
 
 pub fn clean_dir(path: &Path) -> Result<()> {
     if path.exists() {
@@ -90,6 +118,67 @@ impl Compiler {
     pub async fn watch(self, host: String, port: u16) -> Result<()> {
         watch(self, host, port).await
     }
+    // Compile first, then pass all compiled articles to `export`
+    fn with_compiled_articles<T>(
+        &self,
+        export: impl for<'a> FnOnce(
+            &'a TypsiteConfig<'a>,
+            &mut KeyRegistry,
+            HashMap<Key, Article<'a>>,
+        ) -> Result<T>,
+    ) -> Result<T> {
+        let (_, no_error) = self.compile()?;
+        if !no_error {
+            eprintln!("[WARN] Some articles failed to compile, the export may be incomplete");
+        }
+        let config = TypsiteConfig::load(&self.config_path, &self.typst_path, &self.html_cache_path)?;
+        let mut registry = KeyRegistry::new();
+        let mut article_cache = ArticleCache::new(&self.cache_path);
+        article_cache.load(&config, &PathBufs::new(), &mut registry);
+        let articles = article_cache.drain().collect::<HashMap<_, _>>();
+        export(&config, &mut registry, articles)
+    }
+    // Pack the article tree from `root` as EPUB
+    pub fn export_epub(&self, root: &str, epub_path: &Path) -> Result<()> {
+        self.with_compiled_articles(|config, registry, articles| {
+            let root = registry.know(config.format_slug(root), "Root", "export")?;
+            println!("Exporting EPUB...");
+            export_epub(EpubInput {
+                config,
+                articles: &articles,
+                root,
+                assets_path: &self.assets_path,
+                output_path: &self.output_path,
+                epub_path,
+            })
+        })
+    }
+    // Orphan articles and the ones not reachable from the default parent
+    pub fn check_orphans(&self) -> Result<OrphanReport> {
+        self.with_compiled_articles(|config, _, mut articles| {
+            let (parents, backlinks) = analyse_parents_and_backlinks(articles.values().collect());
+            apply_parents_and_backlinks(&mut articles, parents, backlinks);
+            let inverses = analyse_inverse_relations(
+                &proj_options()?.relations,
+                articles.values().collect(),
+            );
+            apply_inverse_relations(&mut articles, inverses);
+            Ok(OrphanReport::new(config, &articles))
+        })
+    }
+    // Dump the site graph of all articles
+    pub fn export_graph(
+        &self,
+        filter: &GraphFilter,
+        format: GraphFormat,
+        graph_path: &Path,
+    ) -> Result<()> {
+        self.with_compiled_articles(|_, _, articles| {
+            println!("Exporting graph...");
+            let graph = SiteGraph::new(articles.values(), filter).render(format)?;
+            write_into_file(graph_path, &graph, "graph")
+        })
+    }
     // return (updated, no error)
     pub fn compile(&self) -> Result<(bool, bool)> {
         //1. Initialize input & config
@@ -135,6 +224,8 @@ impl Compiler {
         // Article Manager, which manages all articles' slugs and paths
         let mut article_cache = ArticleCache::new(&self.cache_path);
 
+        clean_highlight_cache(&self.cache_path);
+
         if overall_compile_needed {
             registry.register_paths(&config, changed_typst_paths.iter());
         }
@@ -145,6 +236,11 @@ impl Compiler {
 
         let proj_options_errors = verify_proj_options(&config, &registry)?;
 
+        // Retried articles are exported again as well, e.g. after a failed PDF export
+        let exported_typst_paths: PathBufs = changed_typst_paths
+            .union(&retry_typst_paths)
+            .cloned()
+            .collect();
         //2. Export typst as HTML
         // Only compile updated typst files into html
         let error_typst_articles = compile_typsts(
@@ -172,6 +268,14 @@ impl Compiler {
             &mut changed_html_paths,
         );
 
+        let stale_page_sources = article_cache.take_stale_pages(&changed_articles);
+        let mut stale_pages = stale_page_sources
+            .iter()
+            .filter_map(|(path, permalink)| {
+                config.output_page_path(path, permalink.as_deref()).ok()
+            })
+            .collect::<PathBufs>();
+
         let changed_article_slugs = changed_articles
             .iter()
             .map(|article| article.slug.clone())
@@ -191,6 +295,23 @@ impl Compiler {
         // Update parents and backlinks into all loaded articles
         apply_parents_and_backlinks(&mut loaded_articles, parents, backlinks);
 
+        // Custom relations declared by other articles
+        let inverses = analyse_inverse_relations(
+            &proj_options()?.relations,
+            loaded_articles.values().collect(),
+        );
+        apply_inverse_relations(&mut loaded_articles, inverses);
+
+        // Take out the articles in embedding or parent loops before composing
+        let error_cycle_articles = remove_cycles(&mut loaded_articles);
+
+        if proj_options().is_ok_and(|options| options.check.orphans) {
+            let report = OrphanReport::new(&config, &loaded_articles);
+            if !report.is_empty() {
+                eprint!("[WARN] {report}");
+            }
+        }
+
         // Load Reverse Dependency Cache
         let mut rev_dep = RevDeps::load(
             &config,
@@ -204,7 +325,34 @@ impl Compiler {
         // and the Reverse Dependencies of each file path are collected. ( Reverse Dependencies = Map<Path -> The files that depend on this file>)
         rev_dep.refresh(&config, &registry, &loaded_articles);
 
+        // Export PDFs of the recompiled articles
+        let typst_error_paths = error_typst_articles
+            .iter()
+            .map(|(path, _)| path.as_path())
+            .collect();
+        let PdfData {
+            mut copied_files,
+            stale_pdfs,
+            error_pdfs,
+        } = export_pdfs(PdfInput {
+            typst: &self.typst,
+            config: &config,
+            typst_path: &self.typst_path,
+            config_path: &self.config_path,
+            cache_path: &self.cache_path,
+            output_path: &self.output_path,
+            articles: &loaded_articles,
+            changed_typst_paths: &exported_typst_paths,
+            errors: &typst_error_paths,
+        });
+
         // 5. Compose pages
+        // Articles may depend on config files and on non-typst files, e.g. included sources
+        let changed_dependency_paths: PathBufs = changed_config_paths
+            .iter()
+            .chain(changed_non_typst.iter())
+            .cloned()
+            .collect();
         let PageData {
             updated_pages,
             cache,
@@ -213,12 +361,108 @@ impl Compiler {
             &config,
             changed_article_slugs,
             changed_typst_paths,
-            &changed_config_paths,
+            &changed_dependency_paths,
             &loaded_articles,
             rev_dep,
             overall_compile_needed,
         )?;
 
+        // Feeds of the updated articles
+        let regenerate_all = overall_compile_needed || !deleted_typst_paths.is_empty();
+        let mut generated_files = Vec::new();
+        generated_files.extend(generate_feeds(&config, &loaded_articles, &cache, regenerate_all));
+        // Theme CSS of classed code highlighting
+        generated_files.extend(generate_code_themes(
+            &config,
+            &changed_config_paths,
+            overall_compile_needed,
+        ));
+        stale_pages.extend(stale_code_themes(&self.output_path));
+        // Sitemap of all successfully composed articles
+        let error_paths = error_pages.iter().map(|(path, _)| path.as_path()).collect();
+        let article_set_changed = regenerate_all
+            || !cache.is_empty()
+            || !error_cache_articles.is_empty()
+            || !error_passing_articles.is_empty();
+        let sitemap = generate_sitemap(&loaded_articles, &error_paths, article_set_changed);
+        stale_pages.extend(stale_sitemap_shards(&self.cache_path, &sitemap));
+        generated_files.extend(sitemap);
+        // Site graph of all successfully composed articles
+        generated_files.extend(generate_graph(&loaded_articles, &error_paths, article_set_changed));
+        // Local graphs around the composed and deleted articles
+        let deleted_slugs = deleted_typst_paths
+            .iter()
+            .filter_map(|path| config.path_to_slug(path).ok())
+            .collect::<Vec<_>>();
+        generated_files.extend(generate_local_graphs(LocalGraphInput {
+            config: &config,
+            articles: &loaded_articles,
+            changed: cache
+                .keys()
+                .map(|slug| slug.as_str())
+                .chain(deleted_slugs.iter().map(String::as_str))
+                .collect(),
+            errors: &error_paths,
+            regenerate_all: overall_compile_needed,
+        }));
+        // Search index of the composed pages
+        let search = generate_search_index(SearchInput {
+            config: &config,
+            cache_path: &self.cache_path,
+            articles: &loaded_articles,
+            page_cache: &cache,
+            deleted: &deleted_typst_paths,
+            errors: &error_paths,
+        });
+        stale_pages.extend(stale_search_shards(&self.cache_path, &search));
+        generated_files.extend(search);
+        // Social cards of the articles whose card inputs are changed
+        copied_files.extend(generate_social_cards(SocialCardInput {
+            typst: &self.typst,
+            config: &config,
+            config_path: &self.config_path,
+            cache_path: &self.cache_path,
+            output_path: &self.output_path,
+            articles: &loaded_articles,
+            errors: &error_paths,
+        }));
+        // Files placed next to the stale and deleted pages
+        let stale_companions = stale_page_sources
+            .into_iter()
+            .chain(deleted_typst_paths.iter().map(|path| (path.clone(), None)))
+            .flat_map(|(path, permalink)| {
+                let permalink = permalink.as_deref();
+                [
+                    config.output_card_path(&path, permalink),
+                    config.output_pdf_path(&path, permalink),
+                    config.output_print_path(&path, permalink),
+                    config.output_local_graph_path(&path, permalink),
+                ]
+            })
+            .filter_map(Result::ok)
+            .filter(|path| self.output_path.join(path).exists())
+            .collect::<Vec<_>>();
+        stale_pages.extend(stale_companions);
+        stale_pages.extend(stale_pdfs);
+        // Files left from the time their options were enabled
+        if let Ok(options) = proj_options() {
+            let disabled = [
+                (!options.print.enable, "print.html"),
+                (!options.local_graph.enable, "graph.json"),
+            ];
+            let stale_files = updated_pages
+                .iter()
+                .flat_map(|(_, page, _)| {
+                    disabled
+                        .iter()
+                        .filter(|(disabled, _)| *disabled)
+                        .map(|(_, extension)| page.with_extension(extension))
+                })
+                .filter(|path| self.output_path.join(path).exists())
+                .collect::<Vec<_>>();
+            stale_pages.extend(stale_files);
+        }
+
         let updated = !loaded_articles.is_empty();
         // 6. Update cache
         article_cache.refresh(&mut registry, loaded_articles);
@@ -231,9 +475,10 @@ impl Compiler {
         error_articles.extend(error_typst_articles);
         error_articles.extend(error_cache_articles);
         error_articles.extend(error_passing_articles);
+        error_articles.extend(error_cycle_articles);
         error_articles.extend(error_pages);
 
-        let no_error = error_articles.is_empty();
+        let no_error = error_articles.is_empty() && error_pdfs.is_empty();
 
         let output = Output {
             monitor,
@@ -243,8 +488,12 @@ impl Compiler {
             output_path: &self.output_path,
             updated_pages,
             deleted_pages,
+            stale_pages,
+            generated_files,
+            copied_files,
             proj_options_errors,
             error_articles,
+            error_exports: error_pdfs,
             changed_non_typst,
             deleted_non_typst,
             changed_assets,
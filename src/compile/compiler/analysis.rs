@@ -1,8 +1,11 @@
 use super::cache::dep::RevDeps;
-use crate::compile::compiler::PathBufs;
+use crate::compile::compiler::{ErrorArticles, PathBufs};
+use crate::compile::error::TypError;
+use crate::compile::options::relation::Relation as CustomRelation;
 use crate::compile::registry::Key;
 use crate::ir::article::Article;
-use std::collections::{HashMap, HashSet};
+use anyhow::anyhow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub(super) type Relation = HashMap<Key, HashSet<Key>>;
 
@@ -59,6 +62,139 @@ pub(super) fn apply_parents_and_backlinks<'b, 'a: 'b>(
         }
     });
 }
+
+// Inverse custom relations, related slug -> inverse name -> declaring slugs
+pub(super) fn analyse_inverse_relations<'b, 'a: 'b>(
+    relations: &[CustomRelation],
+    articles: Vec<&Article<'a>>,
+) -> HashMap<Key, HashMap<String, HashSet<Key>>> {
+    let mut inverses: HashMap<Key, HashMap<String, HashSet<Key>>> = HashMap::new();
+    articles.into_iter().for_each(|article| {
+        let node = article.get_meta_node();
+        for (kind, related) in &node.relations {
+            // Declared by the inverse name, seen from the other article by the name
+            let inverse = match CustomRelation::find(relations, kind) {
+                Some((relation, false)) => relation.inverse.as_ref(),
+                Some((relation, true)) => Some(&relation.name),
+                None => None,
+            };
+            let Some(inverse) = inverse else {
+                continue;
+            };
+            for slug in related {
+                inverses
+                    .entry(slug.clone())
+                    .or_default()
+                    .entry(inverse.clone())
+                    .or_default()
+                    .insert(article.slug.clone());
+            }
+        }
+    });
+    inverses
+}
+
+// All articles are loaded, the inverse relations are replaced rather than extended
+pub(super) fn apply_inverse_relations<'b, 'a: 'b>(
+    articles: &mut HashMap<Key, Article<'a>>,
+    mut inverses: HashMap<Key, HashMap<String, HashSet<Key>>>,
+) {
+    articles.iter_mut().for_each(|(slug, article)| {
+        article.get_mut_meta_node().inverse_relations = inverses.remove(slug).unwrap_or_default();
+    });
+}
+
+// Embedding or parent loops would recurse forever while composing,
+// the articles in them are taken out of the loaded articles and reported as errors.
+pub(super) fn remove_cycles(articles: &mut HashMap<Key, Article>) -> ErrorArticles {
+    fn report(
+        errors: &mut HashMap<Key, TypError>,
+        articles: &HashMap<Key, Article>,
+        cycles: Vec<Vec<Key>>,
+        kind: &str,
+    ) {
+        cycles.into_iter().for_each(|cycle| {
+            let path = cycle
+                .iter()
+                .chain(cycle.first())
+                .map(|slug| slug.as_str())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            cycle
+                .iter()
+                .filter(|slug| articles.contains_key(slug.as_str()))
+                .for_each(|slug| {
+                    errors
+                        .entry(slug.clone())
+                        .or_insert_with(|| TypError::new(slug.clone()))
+                        .add(anyhow!("{kind} cycle: {path}"));
+                });
+        });
+    }
+    let mut errors: HashMap<Key, TypError> = HashMap::new();
+    let embeds = articles
+        .values()
+        .map(|article| {
+            let children = article.get_meta_node().children.iter().cloned().collect();
+            (article.slug.clone(), children)
+        })
+        .collect();
+    report(&mut errors, articles, find_cycles(&embeds), "Embed");
+    // Embed cycles are parent cycles as well, they are reported only once
+    let parents = articles
+        .values()
+        .filter(|article| !errors.contains_key(&article.slug))
+        .map(|article| {
+            let parent = article.get_meta_node().parent.iter().cloned().collect();
+            (article.slug.clone(), parent)
+        })
+        .collect();
+    report(&mut errors, articles, find_cycles(&parents), "Parent");
+
+    let mut errors = errors.into_iter().collect::<Vec<_>>();
+    errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+    errors
+        .into_iter()
+        .filter_map(|(slug, err)| {
+            let article = articles.remove(&slug)?;
+            Some((article.path.to_path_buf(), format!("{err}")))
+        })
+        .collect()
+}
+
+// Each cycle found by the back edges of a depth-first search, in path order
+fn find_cycles(graph: &BTreeMap<Key, Vec<Key>>) -> Vec<Vec<Key>> {
+    fn visit(
+        graph: &BTreeMap<Key, Vec<Key>>,
+        slug: &Key,
+        stack: &mut Vec<Key>,
+        done: &mut HashSet<Key>,
+        cycles: &mut Vec<Vec<Key>>,
+    ) {
+        if let Some(start) = stack.iter().position(|it| it == slug) {
+            cycles.push(stack[start..].to_vec());
+            return;
+        }
+        if done.contains(slug) {
+            return;
+        }
+        stack.push(slug.clone());
+        let mut nexts = graph.get(slug).cloned().unwrap_or_default();
+        nexts.sort();
+        nexts
+            .iter()
+            .for_each(|next| visit(graph, next, stack, done, cycles));
+        stack.pop();
+        done.insert(slug.clone());
+    }
+    let mut cycles = Vec::new();
+    let mut done = HashSet::new();
+    graph
+        .keys()
+        .for_each(|slug| visit(graph, slug, &mut Vec::new(), &mut done, &mut cycles));
+    cycles
+}
+
 pub(super) fn analyse_slugs_to_update_and_load<'b, 'a: 'b>(
     changed_article_slugs: &HashSet<Key>,
     updated_typst_paths: &mut PathBufs,
@@ -166,3 +302,53 @@ pub(super) fn analyse_slugs_to_update_and_load<'b, 'a: 'b>(
 
     (slugs_to_update, slugs_to_load)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_relation_by_inverse_name() {
+        let relations = vec![
+            CustomRelation {
+                name: "prerequisite".to_string(),
+                inverse: Some("required-by".to_string()),
+            },
+            CustomRelation {
+                name: "related".to_string(),
+                inverse: Some("related".to_string()),
+            },
+        ];
+        let find = |kind| {
+            CustomRelation::find(&relations, kind).map(|(it, inverse)| (it.name.as_str(), inverse))
+        };
+        assert_eq!(find("prerequisite"), Some(("prerequisite", false)));
+        assert_eq!(find("required-by"), Some(("prerequisite", true)));
+        assert_eq!(find("related"), Some(("related", false)));
+        assert_eq!(find("supersedes"), None);
+    }
+
+    #[test]
+    fn find_embed_cycles() {
+        let graph: BTreeMap<Key, Vec<Key>> = [
+            ("/a", vec!["/b"]),
+            ("/b", vec!["/c"]),
+            ("/c", vec!["/a"]),
+            ("/d", vec!["/d"]),
+            ("/e", vec!["/a"]),
+        ]
+        .into_iter()
+        .map(|(slug, children)| {
+            (
+                Key::from(slug),
+                children.into_iter().map(Key::from).collect(),
+            )
+        })
+        .collect();
+        let cycles = find_cycles(&graph)
+            .into_iter()
+            .map(|cycle| cycle.iter().map(|it| it.to_string()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(cycles, vec![vec!["/a", "/b", "/c"], vec!["/d"]]);
+    }
+}
@@ -14,10 +14,15 @@ use std::collections::hash_map::Drain;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+pub type StalePages = Vec<(PathBuf, Option<String>)>;
+
 pub struct ArticleCache<'a> {
     cache_article_path: PathBuf,
     cache_html_path: PathBuf,
     cache: HashMap<Key, Article<'a>>,
+    // Output pages left behind by deleted articles or changed permalinks,
+    // as the typst path and the permalink they were placed by
+    stale_pages: StalePages,
 }
 
 impl<'a> ArticleCache<'a> {
@@ -28,6 +33,7 @@ impl<'a> ArticleCache<'a> {
             cache_article_path,
             cache_html_path,
             cache: HashMap::new(),
+            stale_pages: StalePages::new(),
         }
     }
 
@@ -91,10 +97,19 @@ impl<'a> ArticleCache<'a> {
         deleted: &PathBufs,
         registry: &mut KeyRegistry,
     ) -> ErrorArticles {
-        deleted
+        let stale_pages = deleted
             .into_par_iter()
             .map(|path| self.typ_to_json_path(path))
-            .for_each(|path| remove_file_log_err(path, "delete while load cahce"));
+            .filter_map(|path| {
+                let permalink = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|json| serde_json::from_str::<PureArticle>(&json).ok())
+                    .and_then(|pure| Some((pure.path.clone(), Some(pure.permalink()?))));
+                remove_file_log_err(path, "delete while load cahce");
+                permalink
+            })
+            .collect::<StalePages>();
+        self.stale_pages.extend(stale_pages);
 
         let pures = walk_glob!("{}/**/*.json", self.cache_article_path.display())
             .par_bridge()
@@ -119,15 +134,30 @@ impl<'a> ArticleCache<'a> {
             .into_iter()
             .partition(|it| it.is_ok());
 
+        let mut failed = failed
+            .into_iter()
+            .filter_map(|it| it.err())
+            .collect::<Vec<_>>();
         let articles: Vec<(Key, Article<'a>)> = articles
             .into_iter()
-            .filter_map(|it| it.ok().map(|article| (article.slug.clone(), article)))
+            .filter_map(|it| it.ok())
+            .filter_map(|article| {
+                let contents = article.get_meta_contents();
+                let result = registry.register_permalinks(
+                    &article.slug,
+                    contents.permalink(),
+                    contents.aliases(),
+                );
+                match result {
+                    Ok(_) => Some((article.slug.clone(), article)),
+                    Err(err) => {
+                        failed.push(TypError::new_with(article.slug.clone(), vec![err]));
+                        None
+                    }
+                }
+            })
             .collect();
         self.cache.extend(articles);
-        let failed = failed
-            .into_iter()
-            .filter_map(|it| it.err())
-            .collect::<Vec<_>>();
 
         let mut error_articles = HashMap::new();
         self.errors(registry, &mut error_articles, failed);
@@ -163,6 +193,23 @@ impl<'a> ArticleCache<'a> {
         Ok(())
     }
 
+    // Collect the old output pages of the articles whose permalink changed
+    pub fn take_stale_pages(&mut self, changed: &[Article<'a>]) -> StalePages {
+        let stale_pages = changed
+            .iter()
+            .filter_map(|article| {
+                let old = self.cache.get(&article.slug)?;
+                let permalink = old.permalink();
+                if permalink == article.permalink() {
+                    return None;
+                }
+                Some((old.path.to_path_buf(), permalink))
+            })
+            .collect::<StalePages>();
+        self.stale_pages.extend(stale_pages);
+        std::mem::take(&mut self.stale_pages)
+    }
+
     pub fn get(&self, slug: &Arc<str>) -> Option<&Article<'a>> {
         self.cache.get(slug)
     }
@@ -87,11 +87,10 @@ impl<'a> RevDeps {
         let dependents: Vec<(Slug, Dependency, DependencyIndex)> = loaded_articles
             .iter()
             .map(|(slug, article)| {
-                (
-                    slug.clone(),
-                    article.get_depending_components(config),
-                    article.get_dependency(registry),
-                )
+                let mut depending = article.get_depending_components(config, loaded_articles);
+                // The footer lists the backlinks of the related articles
+                depending.extend(article.get_related_paths(loaded_articles));
+                (slug.clone(), depending, article.get_dependency(registry))
             })
             .collect();
 
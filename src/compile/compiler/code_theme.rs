@@ -0,0 +1,50 @@
+use crate::compile::proj_options;
+use crate::config::TypsiteConfig;
+use crate::pass::rewrite::theme_css;
+use crate::util::path::file_ext;
+use std::path::{Path, PathBuf};
+
+use super::{GeneratedFiles, PathBufs};
+
+// Linked as `../assets/rewrite/code_themes.css` by the code rewrites
+const CODE_THEMES_PATH: &str = "rewrite/code_themes.css";
+
+fn classed() -> bool {
+    proj_options()
+        .map(|options| options.code_highlight.classed)
+        .unwrap_or(false)
+}
+
+// Regenerate the CSS of classed highlighting if any theme is changed
+pub fn generate_code_themes(
+    config: &TypsiteConfig,
+    changed_config_paths: &PathBufs,
+    regenerate_all: bool,
+) -> GeneratedFiles {
+    let themes_changed = changed_config_paths
+        .iter()
+        .any(|path| file_ext(path).as_deref() == Some("tmTheme"));
+    if !classed() || !(regenerate_all || themes_changed) {
+        return Vec::new();
+    }
+    let highlight = &config.highlight;
+    let css = highlight
+        .theme_names()
+        .into_iter()
+        .filter_map(|name| {
+            let (light, dark) = highlight.find_theme(name)?;
+            Some(theme_css(name, light, dark))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    vec![(PathBuf::from(CODE_THEMES_PATH), css)]
+}
+
+// The CSS left in the output once classed highlighting is turned off
+pub fn stale_code_themes(output_path: &Path) -> Vec<PathBuf> {
+    let path = PathBuf::from(CODE_THEMES_PATH);
+    if classed() || !output_path.join(&path).is_file() {
+        return Vec::new();
+    }
+    vec![path]
+}
@@ -0,0 +1,560 @@
+use crate::compile::registry::Key;
+use crate::compile::{compile_options, proj_options};
+use crate::config::TypsiteConfig;
+use crate::ir::article::Article;
+use crate::ir::article::data::GlobalData;
+use crate::ir::article::dep::Indexes;
+use crate::ir::article::sidebar::SidebarType;
+use crate::ir::embed::SectionType;
+use crate::util::date::Date;
+use crate::util::html::{escape_xml, html_as_str, local_page_link, sidebar_links, strip_tags};
+use crate::util::path::relative_path;
+use crate::walk_glob;
+use anyhow::{Context, Result};
+use glob::glob;
+use html5gum::{Token, Tokenizer};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+use zip::CompressionMethod;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+const TITLE_KEY: &str = "title";
+const AUTHOR_KEY: &str = "author";
+const LANG_KEY: &str = "lang";
+
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+pub struct EpubInput<'a, 'b> {
+    pub config: &'a TypsiteConfig<'a>,
+    pub articles: &'b HashMap<Key, Article<'a>>,
+    pub root: Key,
+    pub assets_path: &'b Path,
+    pub output_path: &'b Path,
+    pub epub_path: &'b Path,
+}
+
+struct Chapter<'a, 'b> {
+    article: &'b Article<'a>,
+    parent: Option<usize>,
+    // (anchor, title) from the sidebar
+    headings: Vec<(String, String)>,
+    content: String,
+}
+
+// Walk the article tree from the root,
+// embedded articles are inlined into the chapter that embeds them,
+// the other children become sub chapters.
+struct Book<'a, 'b> {
+    articles: &'b HashMap<Key, Article<'a>>,
+    chapters: Vec<Chapter<'a, 'b>>,
+    // public slug -> chapter index
+    targets: HashMap<String, usize>,
+    visited: HashSet<Key>,
+}
+
+impl<'a, 'b> Book<'a, 'b> {
+    fn walk(&mut self, slug: &Key, parent: Option<usize>, inline_in: Option<usize>) {
+        if !self.visited.insert(slug.clone()) {
+            return;
+        }
+        let Some(article) = self.articles.get(slug) else {
+            return;
+        };
+        let chapter = match inline_in {
+            Some(chapter) => chapter,
+            None => {
+                // Composed once the whole tree is walked
+                self.chapters.push(Chapter {
+                    article,
+                    parent,
+                    headings: Vec::new(),
+                    content: String::new(),
+                });
+                self.chapters.len() - 1
+            }
+        };
+        let contents = article.get_meta_contents();
+        self.targets.insert(contents.public_slug(), chapter);
+        contents.aliases().into_iter().for_each(|alias| {
+            self.targets.insert(alias, chapter);
+        });
+
+        // Children in embed order, then the others by slug
+        let embeds = article
+            .get_embeds()
+            .iter()
+            .map(|embed| embed.slug.clone())
+            .collect::<Vec<_>>();
+        let mut others = article
+            .get_meta_node()
+            .children
+            .iter()
+            .filter(|child| !embeds.contains(child))
+            .cloned()
+            .collect::<Vec<_>>();
+        others.sort();
+        embeds
+            .iter()
+            .for_each(|embed| self.walk(embed, parent, Some(chapter)));
+        others
+            .iter()
+            .for_each(|child| self.walk(child, Some(chapter), None));
+    }
+}
+
+// Compose the chapters as their pages, with every article of the tree loaded for the embeds
+fn compose_chapters<'a>(
+    config: &'a TypsiteConfig<'a>,
+    articles: &HashMap<Key, Article<'a>>,
+    chapters: &mut [Chapter<'a, '_>],
+    members: HashSet<Key>,
+) {
+    let pendings = members
+        .into_iter()
+        .map(|slug| (slug, OnceLock::new()))
+        .collect();
+    let indexes = || {
+        articles
+            .keys()
+            .map(|slug| (slug.clone(), Indexes::All))
+            .collect()
+    };
+    let global_data = GlobalData::new(config, articles, pendings, indexes(), indexes());
+    let empty_pos = vec![];
+    chapters.iter_mut().for_each(|chapter| {
+        let pending = chapter.article.get_pending_or_init(&global_data);
+        let (content, full_sidebar, _) = pending.based_on(
+            config,
+            &global_data,
+            Some(&empty_pos),
+            Some(&empty_pos),
+            SidebarType::All,
+            SectionType::Full,
+        );
+        // The full sidebar covers the embedded articles as well
+        chapter.headings = sidebar_links(&full_sidebar.join(""));
+        chapter.content = content.join("");
+    });
+}
+
+pub fn export_epub(input: EpubInput) -> Result<()> {
+    let EpubInput {
+        config,
+        articles,
+        root,
+        assets_path,
+        output_path,
+        epub_path,
+    } = input;
+    let mut book = Book {
+        articles,
+        chapters: Vec::new(),
+        targets: HashMap::new(),
+        visited: HashSet::new(),
+    };
+    book.walk(&root, None, None);
+    let Book {
+        mut chapters,
+        targets,
+        visited,
+        ..
+    } = book;
+    compose_chapters(config, articles, &mut chapters, visited);
+    let root = chapters.first().context("Root article not found")?.article;
+    let root_contents = root.get_meta_contents();
+    let text = |key: &str| {
+        root_contents
+            .get(key)
+            .map(|it| strip_tags(&it))
+            .unwrap_or_default()
+    };
+    let title = Some(text(TITLE_KEY))
+        .filter(|it| !it.is_empty())
+        .unwrap_or_else(|| root.slug.to_string());
+    let author = text(AUTHOR_KEY);
+    let lang = Some(text(LANG_KEY))
+        .filter(|it| !it.is_empty())
+        .unwrap_or_else(|| "en".to_string());
+    let base_url = proj_options()
+        .map(|options| options.site.base_url.clone())
+        .unwrap_or_default();
+    let pretty_url = compile_options()
+        .map(|options| options.pretty_url)
+        .unwrap_or(true);
+
+    // Assets & fonts, copied as they are
+    let assets = walk_glob!("{}/**/*", assets_path.display())
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let relative = relative_path(assets_path, &path).ok()?;
+            Some((path, PathBuf::from("assets").join(relative)))
+        })
+        .collect::<Vec<_>>();
+    // Page layouts of schemas are not for books
+    let stylesheets = assets
+        .iter()
+        .map(|(_, path)| unix_path(path))
+        .filter(|path| path.ends_with(".css") && !path.starts_with("assets/schemas/"))
+        .collect::<Vec<_>>();
+
+    let mut links = LinkResolver {
+        targets: &targets,
+        base_url: base_url.trim_end_matches('/'),
+        output_path,
+        media: HashMap::new(),
+    };
+    let documents = chapters
+        .iter()
+        .enumerate()
+        .map(|(index, chapter)| {
+            let chapter_title = chapter
+                .article
+                .get_meta_contents()
+                .get(TITLE_KEY)
+                .map(|it| strip_tags(&it))
+                .unwrap_or_default();
+            let body = to_xhtml(&chapter.content, &mut |name, value| {
+                links.resolve(name, value)
+            });
+            let styles = stylesheets
+                .iter()
+                .map(|href| format!("<link rel=\"stylesheet\" href=\"{}\" />", escape_xml(href)))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let xhtml = format!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{lang}\" xml:lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\" />\n<title>{title}</title>\n{styles}\n</head>\n<body>\n<section id=\"{id}\" epub:type=\"chapter\">\n<h1>{title}</h1>\n{body}\n</section>\n</body>\n</html>\n",
+                lang = escape_xml(&lang),
+                title = escape_xml(&chapter_title),
+                id = chapter_id(index),
+            );
+            (chapter_file(index), xhtml)
+        })
+        .collect::<Vec<_>>();
+    let media = links.media;
+
+    let nav = nav_document(&chapters, &title, &lang);
+    let identifier = if base_url.is_empty() {
+        format!("urn:typsite:{}", root.slug)
+    } else {
+        let slug = root_contents.public_slug();
+        let slug = if pretty_url { slug } else { format!("{slug}.html") };
+        format!("{}{slug}", base_url.trim_end_matches('/'))
+    };
+    let modified = Date::from_system_time(SystemTime::now())
+        .unwrap_or_default()
+        .rfc3339();
+
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    manifest.push_str(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    documents.iter().enumerate().for_each(|(index, (file, xhtml))| {
+        let mut properties = Vec::new();
+        if xhtml.contains("<svg") {
+            properties.push("svg");
+        }
+        if xhtml.contains("<math") {
+            properties.push("mathml");
+        }
+        let properties = if properties.is_empty() {
+            String::new()
+        } else {
+            format!(" properties=\"{}\"", properties.join(" "))
+        };
+        let id = chapter_id(index);
+        let _ = writeln!(
+            manifest,
+            "    <item id=\"{id}\" href=\"{file}\" media-type=\"application/xhtml+xml\"{properties}/>"
+        );
+        let _ = writeln!(spine, "    <itemref idref=\"{id}\"/>");
+    });
+    assets
+        .iter()
+        .map(|(_, path)| unix_path(path))
+        .chain(media.values().cloned())
+        .enumerate()
+        .for_each(|(index, href)| {
+            let _ = writeln!(
+                manifest,
+                "    <item id=\"res-{index}\" href=\"{}\" media-type=\"{}\"/>",
+                escape_xml(&href),
+                media_type(&href)
+            );
+        });
+    let creator = if author.is_empty() {
+        String::new()
+    } else {
+        format!("    <dc:creator>{}</dc:creator>\n", escape_xml(&author))
+    };
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"uid\" xml:lang=\"{lang}\">\n  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n    <dc:identifier id=\"uid\">{identifier}</dc:identifier>\n    <dc:title>{title}</dc:title>\n    <dc:language>{lang}</dc:language>\n{creator}    <meta property=\"dcterms:modified\">{modified}</meta>\n  </metadata>\n  <manifest>\n{manifest}  </manifest>\n  <spine>\n{spine}  </spine>\n</package>\n",
+        lang = escape_xml(&lang),
+        identifier = escape_xml(&identifier),
+        title = escape_xml(&title),
+    );
+
+    let file = File::create(epub_path).with_context(|| format!("Failed to create {epub_path:?}"))?;
+    let mut zip = ZipWriter::new(file);
+    // `mimetype` must be the first entry and stored uncompressed
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(
+        b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n  <rootfiles>\n    <rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n  </rootfiles>\n</container>\n",
+    )?;
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(opf.as_bytes())?;
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(nav.as_bytes())?;
+    for (file, xhtml) in documents.iter() {
+        zip.start_file(format!("OEBPS/{file}"), deflated)?;
+        zip.write_all(xhtml.as_bytes())?;
+    }
+    let resources = assets
+        .iter()
+        .map(|(from, to)| (from.clone(), unix_path(to)))
+        .chain(media);
+    for (from, to) in resources {
+        let bytes = fs::read(&from).with_context(|| format!("Failed to read {from:?}"))?;
+        zip.start_file(format!("OEBPS/{to}"), deflated)?;
+        zip.write_all(&bytes)?;
+    }
+    zip.finish()?;
+    println!(
+        "  + {epub_path:?} ({} chapters from {})",
+        chapters.len(),
+        root.slug
+    );
+    Ok(())
+}
+
+fn chapter_id(index: usize) -> String {
+    format!("chapter-{}", index + 1)
+}
+
+fn chapter_file(index: usize) -> String {
+    format!("{}.xhtml", chapter_id(index))
+}
+
+fn unix_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn nav_document(chapters: &[Chapter], title: &str, lang: &str) -> String {
+    // Headings of a chapter are followed by its sub chapters
+    fn nav_items(chapters: &[Chapter], parent: Option<usize>, nav: &mut String) {
+        chapters
+            .iter()
+            .enumerate()
+            .filter(|(_, chapter)| chapter.parent == parent)
+            .for_each(|(index, chapter)| {
+                let title = chapter
+                    .article
+                    .get_meta_contents()
+                    .get(TITLE_KEY)
+                    .map(|it| strip_tags(&it))
+                    .filter(|it| !it.is_empty())
+                    .unwrap_or_else(|| chapter.article.slug.to_string());
+                let file = chapter_file(index);
+                let _ = writeln!(nav, "<li><a href=\"{file}\">{}</a>", escape_xml(&title));
+                let has_children = chapters.iter().any(|it| it.parent == Some(index));
+                if !chapter.headings.is_empty() || has_children {
+                    nav.push_str("<ol>\n");
+                    chapter.headings.iter().for_each(|(anchor, heading)| {
+                        let _ = writeln!(
+                            nav,
+                            "<li><a href=\"{file}#{}\">{}</a></li>",
+                            escape_xml(anchor),
+                            escape_xml(heading)
+                        );
+                    });
+                    nav_items(chapters, Some(index), nav);
+                    nav.push_str("</ol>\n");
+                }
+                nav.push_str("</li>\n");
+            });
+    }
+    let mut nav = String::from("<ol>\n");
+    nav_items(chapters, None, &mut nav);
+    nav.push_str("</ol>\n");
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{lang}\" xml:lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\" />\n<title>{title}</title>\n</head>\n<body>\n<nav epub:type=\"toc\" id=\"toc\">\n<h1>{title}</h1>\n{nav}</nav>\n</body>\n</html>\n",
+        lang = escape_xml(lang),
+        title = escape_xml(title),
+    )
+}
+
+struct LinkResolver<'t> {
+    targets: &'t HashMap<String, usize>,
+    base_url: &'t str,
+    output_path: &'t Path,
+    // file in the output -> path in the book
+    media: HashMap<PathBuf, String>,
+}
+
+impl LinkResolver<'_> {
+    // Links to articles in the book are rewritten to their chapters,
+    // local files in the output are packed as media.
+    fn resolve(&mut self, name: &str, value: &str) -> Option<String> {
+        if !matches!(name, "href" | "src") {
+            return None;
+        }
+        let (path, slug, fragment) = local_page_link(value, self.base_url)?;
+        if let Some(&chapter) = self.targets.get(slug) {
+            let fragment = fragment.map(|it| format!("#{it}")).unwrap_or_default();
+            return Some(format!("{}{fragment}", chapter_file(chapter)));
+        }
+        let file = self.output_path.join(path.trim_start_matches('/'));
+        if !file.is_file() {
+            return None;
+        }
+        let media = self
+            .media
+            .entry(file)
+            .or_insert_with(|| format!("media/{}", path.trim_start_matches('/')));
+        Some(media.clone())
+    }
+}
+
+// Serialize HTML as well-formed XHTML,
+// void elements are self-closed and unclosed elements are closed in order.
+fn to_xhtml<F>(html: &str, rewrite_attr: &mut F) -> String
+where
+    F: FnMut(&str, &str) -> Option<String>,
+{
+    let mut xhtml = String::with_capacity(html.len());
+    let mut stack: Vec<String> = Vec::new();
+    let mut skip = 0usize;
+    for token in Tokenizer::new(html).flatten() {
+        match token {
+            Token::StartTag(tag) => {
+                let name = html_as_str(&tag.name).to_lowercase();
+                if name == "script" {
+                    if !tag.self_closing {
+                        skip += 1;
+                    }
+                    continue;
+                }
+                if skip > 0 {
+                    continue;
+                }
+                let _ = write!(xhtml, "<{name}");
+                tag.attributes.iter().for_each(|(key, value)| {
+                    let key = html_as_str(key);
+                    if !is_xml_name(&key) {
+                        return;
+                    }
+                    let value = html_as_str(value);
+                    let value = rewrite_attr(&key, &value).unwrap_or_else(|| value.to_string());
+                    // Boolean attributes need a value in XML
+                    let value = if value.is_empty() && !matches!(key.as_ref(), "alt" | "href" | "src" | "value") {
+                        key.to_string()
+                    } else {
+                        value
+                    };
+                    let _ = write!(xhtml, " {key}=\"{}\"", escape_xml(&value));
+                });
+                if VOID_ELEMENTS.contains(&name.as_str()) || tag.self_closing {
+                    xhtml.push_str(" />");
+                } else {
+                    xhtml.push('>');
+                    stack.push(name);
+                }
+            }
+            Token::EndTag(tag) => {
+                let name = html_as_str(&tag.name).to_lowercase();
+                if name == "script" {
+                    skip = skip.saturating_sub(1);
+                    continue;
+                }
+                if skip > 0 || !stack.contains(&name) {
+                    continue;
+                }
+                while let Some(open) = stack.pop() {
+                    let _ = write!(xhtml, "</{open}>");
+                    if open == name {
+                        break;
+                    }
+                }
+            }
+            Token::String(string) if skip == 0 => {
+                let text = html_as_str(&string);
+                xhtml.push_str(&escape_text(&text));
+            }
+            Token::Comment(comment) if skip == 0 => {
+                let comment = html_as_str(&comment).replace("--", "- -");
+                let _ = write!(xhtml, "<!--{comment}-->");
+            }
+            _ => {}
+        }
+    }
+    while let Some(open) = stack.pop() {
+        let _ = write!(xhtml, "</{open}>");
+    }
+    xhtml
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        && !name.starts_with("xmlns:")
+}
+
+fn media_type(path: &str) -> &'static str {
+    let ext = path.rsplit('.').next().unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        "css" => "text/css",
+        "js" => "application/javascript",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "pdf" => "application/pdf",
+        "html" | "xhtml" => "application/xhtml+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xhtml_well_formed() {
+        let html = "<p>a &amp; b<br><img src=\"/x.png\"><details open><summary>s</summary><div>unclosed</details><script>let a = 1 < 2;</script>";
+        let xhtml = to_xhtml(html, &mut |name, value| {
+            (name == "src").then(|| format!("media{value}"))
+        });
+        assert_eq!(
+            xhtml,
+            "<p>a &amp; b<br /><img src=\"media/x.png\" /><details open=\"open\"><summary>s</summary><div>unclosed</div></details></p>"
+        );
+    }
+}
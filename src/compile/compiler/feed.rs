@@ -0,0 +1,294 @@
+use crate::compile::options::Site;
+use crate::compile::options::feed::{Feed, FeedFormat};
+use crate::compile::registry::Key;
+use crate::compile::{compile_options, proj_options};
+use crate::config::TypsiteConfig;
+use crate::ir::article::Article;
+use crate::ir::article::data::GlobalData;
+use crate::ir::article::dep::Indexes;
+use crate::ir::article::sidebar::SidebarType;
+use crate::ir::embed::SectionType;
+use crate::util::date::Date;
+use crate::util::error::log_err;
+use crate::util::fs::write_into_file;
+use crate::util::html::{escape_xml, strip_tags};
+use anyhow::Context;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use super::GeneratedFiles;
+use super::page_composer::PageCache;
+
+const TITLE_KEY: &str = "title";
+const AUTHOR_KEY: &str = "author";
+const TAXON_KEY: &str = "taxon";
+const MEMBERS_CACHE_DIR: &str = "feed";
+
+struct FeedItem {
+    title: String,
+    author: String,
+    summary: String,
+    content: Option<String>,
+    url: String,
+    date: Option<Date>,
+}
+
+// Regenerate the feeds whose member articles are updated or whose membership changed,
+// or all of them if `regenerate_all` (options changed or articles deleted)
+pub fn generate_feeds<'a>(
+    config: &'a TypsiteConfig<'a>,
+    articles: &HashMap<Key, Article<'a>>,
+    page_cache: &PageCache,
+    regenerate_all: bool,
+) -> GeneratedFiles {
+    let options = match proj_options() {
+        Ok(options) => options,
+        Err(_) => return Vec::new(),
+    };
+    let compile = compile_options();
+    let pretty_url = compile
+        .as_ref()
+        .map(|options| options.pretty_url)
+        .unwrap_or(true);
+    let members_path = compile
+        .map(|options| options.cache_path.join(MEMBERS_CACHE_DIR))
+        .ok();
+    let feeds = options
+        .feeds
+        .iter()
+        .filter_map(|feed| {
+            let members = articles
+                .values()
+                .filter(|article| is_member(feed, article))
+                .collect::<Vec<_>>();
+            let slugs = members
+                .iter()
+                .map(|article| article.slug.to_string())
+                .collect::<HashSet<_>>();
+            // Articles that left the feed are no members anymore, compare with the last membership
+            let members_path = members_path
+                .as_ref()
+                .map(|dir| dir.join(format!("{}.json", feed.path.trim_start_matches('/'))));
+            let previous = members_path
+                .as_ref()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .and_then(|json| serde_json::from_str::<HashSet<String>>(&json).ok());
+            let updated = previous.as_ref() != Some(&slugs)
+                || members
+                    .iter()
+                    .any(|article| page_cache.contains_key(&article.slug));
+            if !regenerate_all && !updated {
+                return None;
+            }
+            if let Some(path) = members_path {
+                let written = serde_json::to_string(&slugs)
+                    .context("Failed to serialize feed members")
+                    .and_then(|json| write_into_file(path, &json, "feed members"));
+                log_err(written);
+            }
+            Some((feed, members))
+        })
+        .collect::<Vec<_>>();
+    // Members out of this compose are composed here for the full content
+    let contents = compose_contents(
+        config,
+        articles,
+        feeds
+            .iter()
+            .filter(|(feed, _)| feed.full_content)
+            .flat_map(|(_, members)| members)
+            .filter(|article| !page_cache.contains_key(&article.slug))
+            .map(|article| article.slug.clone())
+            .collect(),
+    );
+    feeds
+        .into_iter()
+        .map(|(feed, members)| {
+            let items = feed_items(
+                feed,
+                &options.site,
+                members,
+                page_cache,
+                &contents,
+                pretty_url,
+            );
+            let xml = match feed.format {
+                FeedFormat::Atom => atom(feed, &options.site, &items),
+                FeedFormat::Rss => rss(feed, &options.site, &items),
+            };
+            (PathBuf::from(feed.path.trim_start_matches('/')), xml)
+        })
+        .collect()
+}
+
+fn compose_contents<'a>(
+    config: &'a TypsiteConfig<'a>,
+    articles: &HashMap<Key, Article<'a>>,
+    slugs: HashSet<Key>,
+) -> HashMap<Key, String> {
+    if slugs.is_empty() {
+        return HashMap::new();
+    }
+    // Pendings are initialized lazily, loading all covers the embedded articles
+    let pendings = articles
+        .keys()
+        .map(|slug| (slug.clone(), OnceLock::new()))
+        .collect();
+    let indexes = || {
+        articles
+            .keys()
+            .map(|slug| (slug.clone(), Indexes::All))
+            .collect()
+    };
+    let global_data = GlobalData::new(config, articles, pendings, indexes(), indexes());
+    let empty_pos = vec![];
+    slugs
+        .into_iter()
+        .filter_map(|slug| {
+            let pending = articles.get(&slug)?.get_pending_or_init(&global_data);
+            let (content, _, _) = pending.based_on(
+                config,
+                &global_data,
+                Some(&empty_pos),
+                Some(&empty_pos),
+                SidebarType::All,
+                SectionType::Full,
+            );
+            Some((slug, content.join("")))
+        })
+        .collect()
+}
+
+fn is_member(feed: &Feed, article: &Article) -> bool {
+    let contents = article.get_meta_contents();
+    let prefix_matched = feed.prefix.as_ref().is_none_or(|prefix| {
+        let prefix = format!("/{}", prefix.trim_start_matches('/'));
+        article.slug.starts_with(&prefix) || contents.public_slug().starts_with(&prefix)
+    });
+    let taxon_matched = feed.taxon.as_ref().is_none_or(|taxon| {
+        contents
+            .get(TAXON_KEY)
+            .map(|it| strip_tags(&it).eq_ignore_ascii_case(taxon.trim()))
+            .unwrap_or(false)
+    });
+    prefix_matched && taxon_matched
+}
+
+fn feed_items(
+    feed: &Feed,
+    site: &Site,
+    members: Vec<&Article>,
+    page_cache: &PageCache,
+    contents: &HashMap<Key, String>,
+    pretty_url: bool,
+) -> Vec<FeedItem> {
+    let mut items = members
+        .into_iter()
+        .map(|article| {
+            let meta = article.get_meta_contents();
+            let get = |key: &str| meta.get(key).map(|it| it.to_string()).unwrap_or_default();
+            let content = feed.full_content.then(|| {
+                page_cache
+                    .get(&article.slug)
+                    .map(|(body, _, _)| body.join(""))
+                    .or_else(|| contents.get(&article.slug).cloned())
+                    .unwrap_or_default()
+            });
+            FeedItem {
+                title: strip_tags(&get(TITLE_KEY)),
+                author: strip_tags(&get(AUTHOR_KEY)),
+                summary: get(&feed.summary_key),
+                content,
+                url: site.page_url(&meta.public_slug(), pretty_url),
+                date: meta
+                    .get(&feed.date_key)
+                    .and_then(|date| Date::parse(&strip_tags(&date))),
+            }
+        })
+        .collect::<Vec<_>>();
+    // Newest first, undated items at last
+    items.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.url.cmp(&b.url)));
+    items.truncate(feed.limit);
+    items
+}
+
+fn feed_title(feed: &Feed, site: &Site) -> String {
+    feed.title
+        .clone()
+        .or_else(|| (!site.title.is_empty()).then(|| site.title.clone()))
+        .unwrap_or_else(|| "Feed".to_string())
+}
+
+fn atom(feed: &Feed, site: &Site, items: &[FeedItem]) -> String {
+    let feed_url = site.url(&feed.path);
+    let updated = items
+        .iter()
+        .filter_map(|item| item.date)
+        .max()
+        .unwrap_or_default();
+    let mut xml = String::new();
+    let _ = write!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n  <title>{}</title>\n  <id>{}</id>\n  <link href=\"{}\" rel=\"self\"/>\n  <link href=\"{}\"/>\n  <updated>{}</updated>\n",
+        escape_xml(&feed_title(feed, site)),
+        escape_xml(&feed_url),
+        escape_xml(&feed_url),
+        escape_xml(&site.url("")),
+        updated.rfc3339(),
+    );
+    items.iter().for_each(|item| {
+        let url = escape_xml(&item.url);
+        let _ = write!(
+            xml,
+            "  <entry>\n    <title type=\"html\">{}</title>\n    <id>{url}</id>\n    <link href=\"{url}\"/>\n    <updated>{}</updated>\n",
+            escape_xml(&item.title),
+            item.date.unwrap_or_default().rfc3339(),
+        );
+        if !item.author.is_empty() {
+            let _ = writeln!(xml, "    <author><name>{}</name></author>", escape_xml(&item.author));
+        }
+        if !item.summary.is_empty() {
+            let _ = writeln!(xml, "    <summary type=\"html\">{}</summary>", escape_xml(&item.summary));
+        }
+        if let Some(content) = &item.content {
+            let _ = writeln!(xml, "    <content type=\"html\">{}</content>", escape_xml(content));
+        }
+        xml.push_str("  </entry>\n");
+    });
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn rss(feed: &Feed, site: &Site, items: &[FeedItem]) -> String {
+    let title = escape_xml(&feed_title(feed, site));
+    let mut xml = String::new();
+    let _ = write!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n<channel>\n  <title>{title}</title>\n  <link>{}</link>\n  <description>{title}</description>\n  <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        escape_xml(&site.url("")),
+        escape_xml(&site.url(&feed.path)),
+    );
+    items.iter().for_each(|item| {
+        let url = escape_xml(&item.url);
+        let _ = write!(
+            xml,
+            "  <item>\n    <title>{}</title>\n    <link>{url}</link>\n    <guid>{url}</guid>\n",
+            escape_xml(&item.title),
+        );
+        if let Some(date) = item.date {
+            let _ = writeln!(xml, "    <pubDate>{}</pubDate>", date.rfc822());
+        }
+        if !item.author.is_empty() {
+            let _ = writeln!(xml, "    <dc:creator>{}</dc:creator>", escape_xml(&item.author));
+        }
+        let description = item.content.as_ref().unwrap_or(&item.summary);
+        if !description.is_empty() {
+            let _ = writeln!(xml, "    <description>{}</description>", escape_xml(description));
+        }
+        xml.push_str("  </item>\n");
+    });
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
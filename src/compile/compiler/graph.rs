@@ -0,0 +1,442 @@
+use crate::compile::options::graph::{EdgeKind, GraphFormat};
+use crate::compile::registry::Key;
+use crate::compile::{compile_options, proj_options};
+use crate::config::TypsiteConfig;
+use crate::ir::article::Article;
+use crate::util::error::log_err_or_ok;
+use crate::util::html::strip_tags;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use super::GeneratedFiles;
+
+const TITLE_KEY: &str = "title";
+
+pub struct GraphFilter<'f> {
+    // Only articles whose slug starts with the prefix
+    pub prefix: Option<&'f str>,
+    pub edges: &'f [EdgeKind],
+}
+
+#[derive(Debug, Serialize)]
+struct Node {
+    slug: String,
+    url: String,
+    title: String,
+    contents: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+struct Edge {
+    source: String,
+    target: String,
+    kind: EdgeKind,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SiteGraph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl SiteGraph {
+    // Articles and the edges of their `MetaNode`,
+    // edges to articles out of the filter are dropped.
+    pub fn new<'b, 'a: 'b>(
+        articles: impl Iterator<Item = &'b Article<'a>>,
+        filter: &GraphFilter,
+    ) -> Self {
+        let prefix = filter.prefix.map(|prefix| {
+            if prefix.starts_with('/') {
+                prefix.to_string()
+            } else {
+                format!("/{prefix}")
+            }
+        });
+        let articles = articles
+            .filter(|article| {
+                prefix
+                    .as_deref()
+                    .is_none_or(|prefix| article.slug.starts_with(prefix))
+            })
+            .collect::<Vec<_>>();
+        let slugs = articles
+            .iter()
+            .map(|article| article.slug.as_str())
+            .collect::<HashSet<_>>();
+        let pretty_url = compile_options()
+            .map(|options| options.pretty_url)
+            .unwrap_or(true);
+
+        let mut nodes = articles
+            .iter()
+            .map(|article| {
+                let contents = article.get_meta_contents();
+                let slug = contents.public_slug();
+                let url = if pretty_url {
+                    slug
+                } else {
+                    format!("{slug}.html")
+                };
+                let title = contents
+                    .get(TITLE_KEY)
+                    .map(|it| strip_tags(&it))
+                    .unwrap_or_default();
+                let contents = contents
+                    .keys()
+                    .into_iter()
+                    .filter_map(|key| Some((key.to_string(), contents.get(key)?.to_string())))
+                    .collect();
+                Node {
+                    slug: article.slug.to_string(),
+                    url,
+                    title,
+                    contents,
+                }
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.slug.cmp(&b.slug));
+
+        let edges = collect_edges(&articles, &slugs, filter.edges);
+        SiteGraph { nodes, edges }
+    }
+
+    pub fn render(&self, format: GraphFormat) -> Result<String> {
+        match format {
+            GraphFormat::Json => {
+                serde_json::to_string(self).context("Failed to serialize site graph")
+            }
+            GraphFormat::Dot => Ok(self.to_dot()),
+        }
+    }
+
+    // Articles linked by any edge, in both directions
+    fn adjacency(&self) -> HashMap<&str, HashSet<&str>> {
+        let mut adjacency: HashMap<&str, HashSet<&str>> = HashMap::new();
+        self.edges.iter().for_each(|edge| {
+            adjacency
+                .entry(edge.source.as_str())
+                .or_default()
+                .insert(edge.target.as_str());
+            adjacency
+                .entry(edge.target.as_str())
+                .or_default()
+                .insert(edge.source.as_str());
+        });
+        adjacency
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph typsite {\n  node [shape=box];\n");
+        self.nodes.iter().for_each(|node| {
+            let label = if node.title.is_empty() {
+                &node.slug
+            } else {
+                &node.title
+            };
+            let _ = writeln!(
+                dot,
+                "  \"{}\" [label=\"{}\", URL=\"{}\"];",
+                escape_dot(&node.slug),
+                escape_dot(label),
+                escape_dot(&node.url)
+            );
+        });
+        self.edges.iter().for_each(|edge| {
+            let style = match edge.kind {
+                EdgeKind::Parent => "label=\"parent\"",
+                EdgeKind::Child => "label=\"child\", style=bold",
+                EdgeKind::Reference => "label=\"reference\", style=dashed",
+                EdgeKind::Backlink => "label=\"backlink\", style=dotted",
+            };
+            let _ = writeln!(
+                dot,
+                "  \"{}\" -> \"{}\" [{style}];",
+                escape_dot(&edge.source),
+                escape_dot(&edge.target)
+            );
+        });
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Edges of the given kinds between the articles
+fn collect_edges(articles: &[&Article], slugs: &HashSet<&str>, kinds: &[EdgeKind]) -> Vec<Edge> {
+    let mut edges = articles
+        .iter()
+        .flat_map(|article| {
+            let node = article.get_meta_node();
+            let targets = |kind: EdgeKind| -> Vec<&Key> {
+                match kind {
+                    EdgeKind::Parent => node.parent.iter().collect(),
+                    EdgeKind::Child => node.children.iter().collect(),
+                    EdgeKind::Reference => node.references.iter().collect(),
+                    EdgeKind::Backlink => node.backlinks.iter().collect(),
+                }
+            };
+            kinds
+                .iter()
+                .flat_map(|&kind| targets(kind).into_iter().map(move |target| (kind, target)))
+                .filter(|(_, target)| slugs.contains(target.as_str()))
+                .map(|(kind, target)| Edge {
+                    source: article.slug.to_string(),
+                    target: target.to_string(),
+                    kind,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    edges.sort();
+    edges.dedup();
+    edges
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Dump the site graph into the output if `[graph]` is enabled and the article set is changed
+pub fn generate_graph(
+    articles: &HashMap<Key, Article<'_>>,
+    error_paths: &HashSet<&Path>,
+    changed: bool,
+) -> GeneratedFiles {
+    let options = match proj_options() {
+        Ok(options) => options,
+        Err(_) => return Vec::new(),
+    };
+    let graph = &options.graph;
+    if !graph.enable || !changed {
+        return Vec::new();
+    }
+    let filter = GraphFilter {
+        prefix: graph.prefix.as_deref(),
+        edges: &graph.edges,
+    };
+    let articles = articles
+        .values()
+        .filter(|article| !error_paths.contains(article.path.as_ref()));
+    log_err_or_ok(SiteGraph::new(articles, &filter).render(graph.format))
+        .map(|content| vec![(PathBuf::from(&graph.path), content)])
+        .unwrap_or_default()
+}
+
+pub struct LocalGraphInput<'a, 'b> {
+    pub config: &'b TypsiteConfig<'a>,
+    pub articles: &'b HashMap<Key, Article<'a>>,
+    // Slugs of the composed and deleted articles
+    pub changed: HashSet<&'b str>,
+    pub errors: &'b HashSet<&'b Path>,
+    pub regenerate_all: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct LocalNode<'n> {
+    slug: &'n str,
+    title: &'n str,
+    url: &'n str,
+    // Distance from the center article
+    hops: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct LocalGraph<'n> {
+    center: &'n str,
+    nodes: Vec<LocalNode<'n>>,
+    edges: Vec<&'n Edge>,
+}
+
+// Slugs within `depth` hops from the starts, with their distances
+fn within_hops<'n>(
+    adjacency: &HashMap<&'n str, HashSet<&'n str>>,
+    starts: impl IntoIterator<Item = &'n str>,
+    depth: usize,
+) -> HashMap<&'n str, usize> {
+    let mut hops: HashMap<&str, usize> = starts.into_iter().map(|slug| (slug, 0)).collect();
+    let mut frontier = hops.keys().copied().collect::<Vec<_>>();
+    for hop in 1..=depth {
+        frontier = frontier
+            .into_iter()
+            .filter_map(|slug| adjacency.get(slug))
+            .flatten()
+            .copied()
+            .filter(|next| !hops.contains_key(next))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        frontier.iter().for_each(|slug| {
+            hops.insert(slug, hop);
+        });
+    }
+    hops
+}
+
+// Write the N-hop neighbourhood of each article next to its page,
+// only for the articles within N hops from a changed one
+pub fn generate_local_graphs(input: LocalGraphInput) -> GeneratedFiles {
+    let options = match proj_options() {
+        Ok(options) => options,
+        Err(_) => return Vec::new(),
+    };
+    let local_graph = &options.local_graph;
+    if !local_graph.enable {
+        return Vec::new();
+    }
+    let LocalGraphInput {
+        config,
+        articles,
+        changed,
+        errors,
+        regenerate_all,
+    } = input;
+    let depth = local_graph.depth.max(1);
+    // Backlinks are the references seen from the other side
+    let filter = GraphFilter {
+        prefix: None,
+        edges: &[EdgeKind::Parent, EdgeKind::Child, EdgeKind::Reference],
+    };
+    let graph = SiteGraph::new(articles.values(), &filter);
+    let adjacency = graph.adjacency();
+    let to_update = if regenerate_all {
+        articles
+            .keys()
+            .map(|slug| slug.as_str())
+            .collect::<HashSet<_>>()
+    } else {
+        // Deleted articles are out of the graph, start from the articles still linking to them
+        let linking_deleted = articles
+            .values()
+            .filter(|article| {
+                let node = article.get_meta_node();
+                node.parent
+                    .iter()
+                    .chain(node.children.iter())
+                    .chain(node.references.iter())
+                    .chain(node.backlinks.iter())
+                    .any(|other| changed.contains(other.as_str()) && !articles.contains_key(other))
+            })
+            .map(|article| article.slug.as_str());
+        within_hops(
+            &adjacency,
+            changed.iter().copied().chain(linking_deleted),
+            depth,
+        )
+        .into_keys()
+        .collect()
+    };
+    let nodes = graph
+        .nodes
+        .iter()
+        .filter(|node| {
+            articles
+                .get(node.slug.as_str())
+                .is_some_and(|article| !errors.contains(article.path.as_ref()))
+        })
+        .map(|node| (node.slug.as_str(), node))
+        .collect::<HashMap<_, _>>();
+    to_update
+        .into_iter()
+        .filter(|slug| nodes.contains_key(slug))
+        .filter_map(|slug| {
+            let article = articles.get(slug)?;
+            let hops = within_hops(&adjacency, [slug], depth);
+            let mut local_nodes = hops
+                .iter()
+                .filter_map(|(slug, hops)| Some((nodes.get(slug)?, *hops)))
+                .map(|(node, hops)| LocalNode {
+                    slug: &node.slug,
+                    title: &node.title,
+                    url: &node.url,
+                    hops,
+                })
+                .collect::<Vec<_>>();
+            local_nodes.sort_by(|a, b| a.hops.cmp(&b.hops).then(a.slug.cmp(b.slug)));
+            let included = local_nodes
+                .iter()
+                .map(|node| node.slug)
+                .collect::<HashSet<_>>();
+            let edges = graph
+                .edges
+                .iter()
+                .filter(|edge| {
+                    included.contains(edge.source.as_str())
+                        && included.contains(edge.target.as_str())
+                })
+                .collect();
+            let local = LocalGraph {
+                center: slug,
+                nodes: local_nodes,
+                edges,
+            };
+            let path = config
+                .output_local_graph_path(&article.path, article.permalink().as_deref())
+                .ok()?;
+            let json = serde_json::to_string(&local).context("Failed to serialize local graph");
+            log_err_or_ok(json).map(|json| (path, json))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn site_graph_to_dot() {
+        let graph = SiteGraph {
+            nodes: vec![
+                Node {
+                    slug: "/a".to_string(),
+                    url: "/a".to_string(),
+                    title: "Say \"A\"".to_string(),
+                    contents: BTreeMap::new(),
+                },
+                Node {
+                    slug: "/b".to_string(),
+                    url: "/b".to_string(),
+                    title: String::new(),
+                    contents: BTreeMap::new(),
+                },
+            ],
+            edges: vec![Edge {
+                source: "/a".to_string(),
+                target: "/b".to_string(),
+                kind: EdgeKind::Reference,
+            }],
+        };
+        assert_eq!(
+            graph.to_dot(),
+            "digraph typsite {\n  node [shape=box];\n  \"/a\" [label=\"Say \\\"A\\\"\", URL=\"/a\"];\n  \"/b\" [label=\"/b\", URL=\"/b\"];\n  \"/a\" -> \"/b\" [label=\"reference\", style=dashed];\n}\n"
+        );
+    }
+
+    #[test]
+    fn hops_from_center() {
+        // Edges are walked in both directions
+        let edge = |source: &str, target: &str, kind| Edge {
+            source: source.to_string(),
+            target: target.to_string(),
+            kind,
+        };
+        let graph = SiteGraph {
+            nodes: Vec::new(),
+            edges: vec![
+                edge("/a", "/b", EdgeKind::Child),
+                edge("/c", "/b", EdgeKind::Reference),
+                edge("/d", "/c", EdgeKind::Parent),
+            ],
+        };
+        let adjacency = graph.adjacency();
+        let hops = within_hops(&adjacency, ["/a"], 2);
+        assert_eq!(hops.len(), 3);
+        assert_eq!(hops["/a"], 0);
+        assert_eq!(hops["/b"], 1);
+        assert_eq!(hops["/c"], 2);
+    }
+}
@@ -2,6 +2,7 @@ use crate::compile::registry::KeyRegistry;
 use crate::config::TypsiteConfig;
 use crate::ir::article::Article;
 use crate::pass::pass_pure;
+use crate::pass::tokenizer::scan_permalinks;
 use anyhow::*;
 use rayon::prelude::*;
 use std::fs;
@@ -17,28 +18,46 @@ pub fn pass_html<'b, 'a: 'b>(
     registry: &mut KeyRegistry,
     changed_html_paths: &mut Vec<PathBuf>,
 ) -> (Vec<Article<'a>>, ErrorArticles) {
-    // Partition into success and errors
-    let (success, errors): (Vec<_>, Vec<_>) = changed_html_paths
+    let prepared = changed_html_paths
         .iter()
         .map(|html_path| {
             registry
                 .register_article_path(config, html_path)
-                .map(|(slug, path)| (slug, path, html_path))
+                .and_then(|(slug, path)| {
+                    let html = fs::read_to_string(html_path)
+                        .with_context(|| format!("Read file {html_path:?} failed."))?;
+                    Ok((slug, path, html))
+                })
         })
-        .collect::<Vec<Result<_>>>()
+        .collect::<Vec<Result<_>>>();
+    // Permalinks of the changed articles may be changed or swapped,
+    // so remove all of them before registering the new ones
+    prepared
+        .iter()
+        .flatten()
+        .for_each(|(slug, ..)| registry.remove_permalinks(slug));
+    let prepared = prepared
+        .into_iter()
+        .map(|result| {
+            result.and_then(|(slug, path, html)| {
+                let (permalink, aliases) = scan_permalinks(&html)?;
+                registry.register_permalinks(&slug, permalink, aliases)?;
+                Ok((slug, path, html))
+            })
+        })
+        .collect::<Vec<Result<_>>>();
+    let registry_ref: &KeyRegistry = registry;
+    // Partition into success and errors
+    let (success, errors): (Vec<_>, Vec<_>) = prepared
         .into_iter()
         .enumerate()
         .par_bridge() // until here, we use rayon to
         // parallelize the pure pass
         .map(|(i, result)| match result {
-            Ok((slug, typst_path, html_path)) => {
-                let result = fs::read_to_string(html_path)
-                    .with_context(|| format!("Read file {html_path:?} failed."))
-                    .map(|html| {
-                        let cache = cache.get(&slug);
-                        pass_pure(config, registry, typst_path, slug.clone(),cache, &html)
-                    });
-                (i, result)
+            Ok((slug, typst_path, html)) => {
+                let cache = cache.get(&slug);
+                let result = pass_pure(config, registry_ref, typst_path, slug.clone(), cache, &html);
+                (i, Ok(result))
             }
             Err(e) => (i, Err(e)),
         })
@@ -55,6 +55,8 @@ pub fn initialize<'a>(
     let retry_typst_paths = monitor.retry_typsts();
     let retry_html_paths = monitor.retry_htmls();
 
+    // Options go first, the footer components of relations are loaded by their names
+    init_options_toml(config_path)?;
     let config =
         TypsiteConfig::load(config_path, typst_path, html_cache_path).with_context(|| {
             format!("Loading '{config_path:?}' failed, try to init Typsite first by: typsite init")
@@ -95,7 +97,6 @@ pub fn initialize<'a>(
         println!("Packages changed, reloading...");
     }
 
-    init_options_toml(config_path)?;
     let lib_files = &proj_options()?.typst_lib.files;
     let lib_dirs = &proj_options()?.typst_lib.dirs;
     let libs_changed = changed_typst_paths
@@ -0,0 +1,123 @@
+use crate::compile::proj_options;
+use crate::compile::registry::Key;
+use crate::config::TypsiteConfig;
+use crate::ir::article::Article;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+
+pub struct OrphanReport {
+    // Articles without parent, backlinks, nor embedded anywhere
+    pub orphans: Vec<Key>,
+    // (article, parent) of which the parent chain doesn't lead to the root
+    pub unreachable: Vec<(Key, Key)>,
+}
+
+impl OrphanReport {
+    // Run on the nodes after `apply_parents_and_backlinks`
+    pub fn new(config: &TypsiteConfig, articles: &HashMap<Key, Article>) -> Self {
+        let root = proj_options().ok().and_then(|options| {
+            options
+                .default_metadata
+                .graph
+                .default_parent_slug(config, |slug| {
+                    articles.get(slug.as_str()).map(|it| it.slug.clone())
+                })
+        });
+        Self::from_nodes(articles, root.as_ref())
+    }
+
+    fn from_nodes(articles: &HashMap<Key, Article>, root: Option<&Key>) -> Self {
+        let mut orphans = articles
+            .values()
+            .filter(|article| Some(&article.slug) != root)
+            .filter(|article| {
+                let node = article.get_meta_node();
+                node.parent.is_none() && node.parents.is_empty() && node.backlinks.is_empty()
+            })
+            .map(|article| article.slug.clone())
+            .collect::<Vec<_>>();
+        orphans.sort();
+
+        let parent_of = |slug: &Key| {
+            articles
+                .get(slug)
+                .and_then(|article| article.get_meta_node().parent.clone())
+        };
+        let mut unreachable = root
+            .map(|root| {
+                articles
+                    .values()
+                    .filter_map(|article| Some((article, parent_of(&article.slug)?)))
+                    .filter(|(article, _)| !reaches(&article.slug, root, parent_of))
+                    .map(|(article, parent)| (article.slug.clone(), parent))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        unreachable.sort();
+        Self {
+            orphans,
+            unreachable,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orphans.is_empty() && self.unreachable.is_empty()
+    }
+}
+
+// If the parent chain from the slug leads to the root,
+// the default parent doesn't count, a chain broken by an article without parent is not reachable.
+fn reaches(slug: &Key, root: &Key, parent_of: impl Fn(&Key) -> Option<Key>) -> bool {
+    let mut visited = HashSet::new();
+    let mut current = Some(slug.clone());
+    while let Some(slug) = current {
+        if &slug == root {
+            return true;
+        }
+        if !visited.insert(slug.clone()) {
+            return false;
+        }
+        current = parent_of(&slug);
+    }
+    false
+}
+
+impl Display for OrphanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.orphans.is_empty() {
+            writeln!(
+                f,
+                "Orphan articles (no parent, backlinks, nor embedded anywhere):"
+            )?;
+            for slug in &self.orphans {
+                writeln!(f, "  - {slug}")?;
+            }
+        }
+        if !self.unreachable.is_empty() {
+            writeln!(f, "Articles not reachable from the root by their parents:")?;
+            for (slug, parent) in &self.unreachable {
+                writeln!(f, "  - {slug} (parent: {parent})")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parent_chain_reaches_root() {
+        let parents: HashMap<Key, Key> =
+            [("/c", "/b"), ("/b", "/root"), ("/x", "/y"), ("/y", "/x")]
+                .into_iter()
+                .map(|(child, parent)| (Key::from(child), Key::from(parent)))
+                .collect();
+        let parent_of = |slug: &Key| parents.get(slug).cloned();
+        let root = Key::from("/root");
+        assert!(reaches(&Key::from("/c"), &root, parent_of));
+        assert!(!reaches(&Key::from("/x"), &root, parent_of));
+        assert!(!reaches(&Key::from("/z"), &root, parent_of));
+    }
+}
@@ -1,5 +1,5 @@
 use super::cache::monitor::Monitor;
-use super::{ErrorArticles, PathBufs, UpdatedPages};
+use super::{CopiedFiles, ErrorArticles, GeneratedFiles, PathBufs, UpdatedPages};
 use crate::util::error::log_err;
 use crate::util::fs::{remove_file, write_into_file};
 use crate::util::path::relative_path;
@@ -16,8 +16,13 @@ pub struct Output<'a> {
     pub output_path: &'a Path,
     pub updated_pages: UpdatedPages<'a>,
     pub deleted_pages: PathBufs,
+    pub stale_pages: PathBufs,
+    pub generated_files: GeneratedFiles,
+    pub copied_files: CopiedFiles,
     pub proj_options_errors: Vec<String>,
     pub error_articles: ErrorArticles,
+    // Errors of the files exported from the articles, whose pages are kept
+    pub error_exports: ErrorArticles,
     pub changed_non_typst: PathBufs,
     pub deleted_non_typst: PathBufs,
     pub changed_assets: PathBufs,
@@ -27,7 +32,11 @@ impl<'a> Output<'a> {
     fn unchanged(&self) -> bool {
         self.updated_pages.is_empty()
             && self.deleted_pages.is_empty()
+            && self.stale_pages.is_empty()
+            && self.generated_files.is_empty()
+            && self.copied_files.is_empty()
             && self.error_articles.is_empty()
+            && self.error_exports.is_empty()
             && self.changed_non_typst.is_empty()
             && self.deleted_non_typst.is_empty()
             && self.changed_assets.is_empty()
@@ -46,8 +55,12 @@ pub fn sync_files_to_output<'a>(output: Output<'a>) {
         output_path,
         updated_pages,
         deleted_pages,
+        stale_pages,
+        generated_files,
+        copied_files,
         proj_options_errors,
         error_articles,
+        error_exports,
         changed_non_typst,
         deleted_non_typst,
         changed_assets,
@@ -70,8 +83,12 @@ pub fn sync_files_to_output<'a>(output: Output<'a>) {
         deleted_non_typst,
     );
     sync_files(assets_path, output_path, changed_assets, deleted_assets);
-    write_pages(&monitor, typst_path, output_path, updated_pages);
+    remove_stale_pages(output_path, stale_pages);
+    write_pages(&monitor, output_path, updated_pages);
+    write_generated_files(output_path, generated_files);
+    copy_files(output_path, copied_files);
     remove_pages(typst_path, output_path, deleted_pages);
+    retry_exports(&monitor, error_exports);
     remove_errors(
         monitor,
         error_articles,
@@ -81,15 +98,12 @@ pub fn sync_files_to_output<'a>(output: Output<'a>) {
     );
 }
 
-fn write_pages(monitor: &Monitor, typst_path: &Path, output_path: &Path, output: UpdatedPages) {
+fn write_pages(monitor: &Monitor, output_path: &Path, output: UpdatedPages) {
     output
         .into_iter()
-        .map(|(typ_path, html)| {
+        .map(|(typ_path, page_path, html)| {
             monitor.remove_retry_hash(&typ_path);
-            let html_path = relative_path(typst_path, &typ_path)
-                .map(|it| it.with_extension("html"))
-                .unwrap();
-            let output_path = output_path.join(html_path);
+            let output_path = output_path.join(page_path);
             if output_path.exists() {
                 println!("  ∓ {output_path:#?}");
             } else {
@@ -99,6 +113,48 @@ fn write_pages(monitor: &Monitor, typst_path: &Path, output_path: &Path, output:
         })
         .for_each(log_err);
 }
+fn write_generated_files(output_path: &Path, files: GeneratedFiles) {
+    files
+        .into_iter()
+        .map(|(path, content)| {
+            let output_path = output_path.join(path);
+            if output_path.exists() {
+                println!("  ∓ {output_path:#?}");
+            } else {
+                println!("  + {output_path:#?}");
+            }
+            write_into_file(output_path, &content, "generated")
+        })
+        .for_each(log_err);
+}
+fn copy_files(output_path: &Path, files: CopiedFiles) {
+    files
+        .into_par_iter()
+        .map(|(from, to)| {
+            let output_path = output_path.join(to);
+            let exists = output_path.exists();
+            if let Some(parent) = output_path.parent() {
+                create_dir_all(parent).with_context(|| {
+                    format!("Create directory failed while creating file: {output_path:#?}")
+                })?;
+            }
+            fs::copy(&from, &output_path)
+                .with_context(|| format!("Copy {from:#?} to {output_path:#?}  failed."))?;
+            if exists {
+                println!("  ∓ {output_path:#?}");
+            } else {
+                println!("  + {output_path:#?}");
+            }
+            Ok(())
+        })
+        .for_each(log_err);
+}
+fn remove_stale_pages(output_path: &Path, stale_pages: PathBufs) {
+    stale_pages
+        .into_par_iter()
+        .map(|page_path| remove_output(output_path, &output_path.join(page_path), output_path))
+        .for_each(log_err);
+}
 fn remove_pages(typst_path: &Path, output_path: &Path, deleted_pages: PathBufs) {
     deleted_pages
         .into_par_iter()
@@ -159,6 +215,14 @@ fn remove_output(parent: &Path, file: &Path, output_path: &Path) -> Result<()> {
     Ok(())
 }
 
+// Retried after the pages are written, which clear the retry of their articles
+fn retry_exports(monitor: &Monitor, error_exports: ErrorArticles) {
+    error_exports.into_iter().for_each(|(path, error)| {
+        monitor.retry_next_time(&path);
+        eprintln!("{error}");
+    });
+}
+
 fn remove_errors(
     monitor: Monitor,
     error_articles: ErrorArticles,
@@ -1,5 +1,6 @@
 use super::cache::dep::RevDeps;
-use crate::compile::error::TypResult;
+use crate::compile::error::{TypError, TypResult};
+use crate::compile::proj_options;
 use crate::compile::registry::Key;
 use crate::config::TypsiteConfig;
 use crate::ir::article::Article;
@@ -14,6 +15,7 @@ use std::collections::{HashMap, HashSet};
 use std::result::Result::Ok;
 use std::sync::{Arc, OnceLock};
 
+use super::print::{compose_print_page, print_tree_members};
 use super::{ErrorArticles, PathBufs, UpdatedPages, analyse_slugs_to_update_and_load};
 
 pub type PageCache = HashMap<Key, (Vec<String>, Vec<String>, Vec<String>)>;
@@ -38,7 +40,7 @@ pub fn compose_pages<'c, 'b: 'c, 'a: 'b>(
     // Collect all slugs that need to update
     // - If a file is changed, all files that depend on it need to be updated
     // - If an article is changed, itself needs to be updated
-    let (slugs_to_update, slugs_to_load) = analyse_slugs_to_update_and_load(
+    let (slugs_to_update, mut slugs_to_load) = analyse_slugs_to_update_and_load(
         &changed_article_slugs,
         &mut updated_typst_paths,
         changed_config_paths,
@@ -57,6 +59,25 @@ pub fn compose_pages<'c, 'b: 'c, 'a: 'b>(
             overall_compile_needed,
         );
 
+    // Print views cover their article trees, so they're updated with any member,
+    // and every member is loaded to be composed
+    let print_trees = if proj_options().is_ok_and(|options| options.print.enable) {
+        loaded_articles
+            .values()
+            .filter(|article| article.schema.content)
+            .map(|article| {
+                (
+                    &article.slug,
+                    print_tree_members(loaded_articles, &article.slug),
+                )
+            })
+            .filter(|(_, members)| !members.is_disjoint(&slugs_to_update))
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+    slugs_to_load.extend(print_trees.values().flatten().cloned());
+
     let pendings = slugs_to_load
         .into_iter()
         .map(|slug| (slug, OnceLock::new()))
@@ -92,6 +113,14 @@ pub fn compose_pages<'c, 'b: 'c, 'a: 'b>(
         })
         .for_each(|meta_contents| meta_contents.init_parent(&global_data));
 
+    // Targets of custom relations need the backlink to be listed in the footer of the declaring articles
+    let related = slugs_to_update
+        .iter()
+        .filter_map(|slug| global_data.article(slug))
+        .flat_map(|article| article.get_meta_node().relations.values().flatten())
+        .cloned()
+        .collect::<HashSet<_>>();
+
     let empty_pos = vec![];
     let final_cache = slugs_to_update
         .iter()
@@ -123,13 +152,23 @@ pub fn compose_pages<'c, 'b: 'c, 'a: 'b>(
             final_cache[slug]
                 .set((content, full_sidebar, embed_sidebar))
                 .unwrap();
+            // Invalid embeds fail the embedding article
+            if !pending.errors.is_empty() {
+                let errors = pending.errors.iter().map(|err| anyhow!("{err}")).collect();
+                return Err(TypError::new_with(article.slug.clone(), errors));
+            }
             let node = &article.get_meta_node();
             if !node.backlinks.is_empty() {
                 // If the article has Backlinks -> it's cited, the citing articles need the reference.
                 global_data.init_reference(article, &content_str, &sidebar_str)?;
             }
-            if !node.references.is_empty() {
+            if !node.references.is_empty()
+                || !node.relations.is_empty()
+                || !node.inverse_relations.is_empty()
+                || related.contains(slug)
+            {
                 // If the article has References -> it's citing other articles, the cited articles need the backlink.
+                // Related articles are listed by their backlinks as well.
                 global_data.init_backlink(article, &content_str, &sidebar_str)?;
             }
             Ok((article, (content_str, sidebar_str)))
@@ -148,7 +187,12 @@ pub fn compose_pages<'c, 'b: 'c, 'a: 'b>(
                     sidebar.as_str(),
                     &global_data,
                 )
-                .map(|html| (article.path.clone(), html))
+                .and_then(|html| {
+                    let page_path = config
+                        .output_page_path(&article.path, article.permalink().as_deref())
+                        .map_err(|err| TypError::new_with(article.slug.clone(), vec![err]))?;
+                    Ok((article.path.clone(), page_path, html))
+                })
             })
         })
         .partition(|res| res.is_ok());
@@ -157,7 +201,24 @@ pub fn compose_pages<'c, 'b: 'c, 'a: 'b>(
         .par_bridge()
         .map(|(slug, lock)| (slug, lock.into_inner().unwrap()))
         .collect();
-    let updated_pages: UpdatedPages = output.into_iter().flatten().collect();
+    let mut updated_pages: UpdatedPages = output.into_iter().flatten().collect();
+    let failed_slugs = failed
+        .iter()
+        .filter_map(|it| it.as_ref().err())
+        .map(|err| err.slug.clone())
+        .collect::<HashSet<_>>();
+    // Print views of the trees with updated members
+    let print_pages = print_trees
+        .into_par_iter()
+        .filter(|(slug, _)| !failed_slugs.contains(*slug))
+        .filter_map(|(slug, _)| global_data.article(slug))
+        .filter_map(|article| {
+            compose_print_page(config, &global_data, &cache, article)
+                .map_err(|err| eprintln!("[WARN] Print view failed, skip:\n{err}"))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    updated_pages.extend(print_pages);
     let error_articles = failed
         .into_iter()
         .filter_map(|it| it.err())
@@ -0,0 +1,123 @@
+use crate::compile::error::TypError;
+use crate::compile::registry::Key;
+use crate::config::TypsiteConfig;
+use crate::ir::article::Article;
+use crate::util::error::TypsiteError;
+use crate::util::fs::create_all_parent_dir;
+use anyhow::{Context, Error};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+
+use super::{CopiedFiles, ErrorArticles, PathBufs};
+
+const PDF_CACHE_DIR: &str = "pdf";
+
+pub struct PdfInput<'a, 'b> {
+    pub typst: &'b str,
+    pub config: &'b TypsiteConfig<'a>,
+    pub typst_path: &'b Path,
+    pub config_path: &'b Path,
+    pub cache_path: &'b Path,
+    pub output_path: &'b Path,
+    pub articles: &'b HashMap<Key, Article<'a>>,
+    pub changed_typst_paths: &'b PathBufs,
+    pub errors: &'b HashSet<&'b Path>,
+}
+
+pub struct PdfData {
+    pub copied_files: CopiedFiles,
+    // PDFs of the articles which are not exported anymore
+    pub stale_pdfs: PathBufs,
+    // Failed exports, retried next time like the failed pages
+    pub error_pdfs: ErrorArticles,
+}
+
+// Export the changed articles with `pdf` metaoption as PDF,
+// failures are reported and retried, but the HTML output is not blocked.
+pub fn export_pdfs(input: PdfInput) -> PdfData {
+    let PdfInput {
+        typst,
+        config,
+        typst_path,
+        config_path,
+        cache_path,
+        output_path,
+        articles,
+        changed_typst_paths,
+        errors,
+    } = input;
+    let cache_dir = cache_path.join(PDF_CACHE_DIR);
+    let changed = articles
+        .values()
+        .filter(|article| changed_typst_paths.contains(article.path.as_ref()))
+        .filter(|article| !errors.contains(article.path.as_ref()))
+        .filter_map(|article| {
+            let pdf_path = config
+                .output_pdf_path(&article.path, article.permalink().as_deref())
+                .ok()?;
+            Some((article, pdf_path))
+        })
+        .collect::<Vec<_>>();
+
+    let stale_pdfs = changed
+        .iter()
+        .filter(|(article, pdf_path)| {
+            !article.get_meta_options().pdf && output_path.join(pdf_path).exists()
+        })
+        .map(|(_, pdf_path)| pdf_path.clone())
+        .collect();
+
+    let (exported, failed): (Vec<_>, Vec<_>) = changed
+        .into_par_iter()
+        .filter(|(article, _)| article.get_meta_options().pdf)
+        .map(|(article, pdf_path)| {
+            let cache_pdf = cache_dir.join(&pdf_path);
+            compile_pdf(typst, typst_path, config_path, &article.path, &cache_pdf)
+                .map(|_| (cache_pdf, pdf_path))
+                .map_err(|err| {
+                    let err = TypError::new_with(article.slug.clone(), vec![err]);
+                    (article.path.to_path_buf(), format!("PDF export failed:\n{err}"))
+                })
+        })
+        .partition(|result| result.is_ok());
+    let copied_files = exported.into_iter().filter_map(Result::ok).collect();
+    let error_pdfs = failed.into_iter().filter_map(Result::err).collect();
+
+    PdfData {
+        copied_files,
+        stale_pdfs,
+        error_pdfs,
+    }
+}
+
+fn compile_pdf(
+    typst: &str,
+    root: &Path,
+    config: &Path,
+    input: &Path,
+    output: &Path,
+) -> anyhow::Result<()> {
+    create_all_parent_dir(output)?;
+    let font_path = config.join("assets/fonts");
+    let output = Command::new(typst)
+        .arg("c")
+        .arg(input)
+        .arg("--root")
+        .arg(root)
+        .arg("-f=pdf")
+        .arg("--features")
+        .arg("html")
+        .arg("--font-path")
+        .arg(font_path)
+        .arg(output)
+        .output()
+        .with_context(|| format!("Typst compile to PDF failed: {}", input.display()))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(Error::new(TypsiteError::Typst(stderr.to_string())))
+    }
+}
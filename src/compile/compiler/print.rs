@@ -0,0 +1,310 @@
+use crate::compile::error::{TypError, TypResult};
+use crate::compile::proj_options;
+use crate::compile::registry::Key;
+use crate::config::TypsiteConfig;
+use crate::ir::article::Article;
+use crate::ir::article::data::GlobalData;
+use crate::ir::article::sidebar::SidebarType;
+use crate::ir::embed::SectionType;
+use crate::pass::pass_schema;
+use crate::util::html::{OutputHtml, escape_xml, local_page_link, sidebar_links, strip_tags};
+use anyhow::anyhow;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::page_composer::PageCache;
+
+const TITLE_KEY: &str = "title";
+
+struct Chapter<'a, 'b> {
+    article: &'b Article<'a>,
+    parent: Option<usize>,
+    // The root chapter is anchored by the section of its schema
+    anchor: String,
+    content: String,
+    full_sidebar: String,
+}
+
+// Walk the article tree like the EPUB export,
+// embedded articles are already in the content of the chapter that embeds them,
+// the other children become sub chapters.
+struct Handout<'a, 'b, 'c> {
+    config: &'a TypsiteConfig<'a>,
+    global_data: &'c GlobalData<'a, 'b, 'c>,
+    cache: &'c PageCache,
+    chapters: Vec<Chapter<'a, 'b>>,
+    // public slug -> anchor of the chapter
+    targets: HashMap<String, String>,
+    visited: HashSet<Key>,
+}
+
+impl<'c, 'b: 'c, 'a: 'b> Handout<'a, 'b, 'c> {
+    fn walk(
+        &mut self,
+        slug: &Key,
+        parent: Option<usize>,
+        inline_in: Option<usize>,
+    ) -> TypResult<()> {
+        if !self.visited.insert(slug.clone()) {
+            return Ok(());
+        }
+        let Some(article) = self.global_data.article(slug) else {
+            return Ok(());
+        };
+        let chapter = match inline_in {
+            Some(chapter) => chapter,
+            None => {
+                let (content, full_sidebar) = self.compose(article)?;
+                self.chapters.push(Chapter {
+                    article,
+                    parent,
+                    anchor: slug[1..].to_string(),
+                    content,
+                    full_sidebar,
+                });
+                self.chapters.len() - 1
+            }
+        };
+        let anchor = self.chapters[chapter].anchor.clone();
+        let contents = article.get_meta_contents();
+        self.targets.insert(contents.public_slug(), anchor.clone());
+        contents.aliases().into_iter().for_each(|alias| {
+            self.targets.insert(alias, anchor.clone());
+        });
+
+        // Children in embed order, then the others by slug
+        let embeds = article
+            .get_embeds()
+            .iter()
+            .map(|embed| embed.slug.clone())
+            .collect::<Vec<_>>();
+        let mut others = article
+            .get_meta_node()
+            .children
+            .iter()
+            .filter(|child| !embeds.contains(child))
+            .cloned()
+            .collect::<Vec<_>>();
+        others.sort();
+        for embed in &embeds {
+            self.walk(embed, parent, Some(chapter))?;
+        }
+        for child in &others {
+            self.walk(child, Some(chapter), None)?;
+        }
+        Ok(())
+    }
+
+    // (content, full sidebar) of a chapter, composed as its own page
+    fn compose(&self, article: &'b Article<'a>) -> TypResult<(String, String)> {
+        if let Some((content, full_sidebar, _)) = self.cache.get(&article.slug) {
+            return Ok((content.join(""), full_sidebar.join("")));
+        }
+        // Members of the tree are loaded by `print_tree_members`
+        if !self.global_data.has_pending(article) {
+            let err = anyhow!("Chapter {} is not loaded", article.slug);
+            return Err(TypError::new_with(article.slug.clone(), vec![err]));
+        }
+        let empty_pos = vec![];
+        let pending = article.get_pending_or_init(self.global_data);
+        let (content, full_sidebar, _) = pending.based_on(
+            self.config,
+            self.global_data,
+            Some(&empty_pos),
+            Some(&empty_pos),
+            SidebarType::All,
+            SectionType::Full,
+        );
+        Ok((content.join(""), full_sidebar.join("")))
+    }
+}
+
+// Articles of the print view of an article, its children and embeds in depth,
+// which the print view depends on
+pub fn print_tree_members(articles: &HashMap<Key, Article>, slug: &Key) -> HashSet<Key> {
+    let mut members = HashSet::new();
+    let mut stack = vec![slug.clone()];
+    while let Some(slug) = stack.pop() {
+        let Some(article) = articles.get(&slug) else {
+            continue;
+        };
+        if !members.insert(slug) {
+            continue;
+        }
+        stack.extend(article.get_embeds().iter().map(|embed| embed.slug.clone()));
+        stack.extend(article.get_meta_node().children.iter().cloned());
+    }
+    members
+}
+
+// Compose the print view of an article,
+// a single page of the article and its article tree, linked by in-page anchors
+pub fn compose_print_page<'c, 'b: 'c, 'a: 'b>(
+    config: &'a TypsiteConfig<'a>,
+    global_data: &'c GlobalData<'a, 'b, 'c>,
+    cache: &'c PageCache,
+    article: &'b Article<'a>,
+) -> TypResult<(Arc<Path>, PathBuf, OutputHtml<'a>)> {
+    let mut handout = Handout {
+        config,
+        global_data,
+        cache,
+        chapters: Vec::new(),
+        targets: HashMap::new(),
+        visited: HashSet::new(),
+    };
+    handout.walk(&article.slug, None, None)?;
+    let Handout {
+        chapters, targets, ..
+    } = handout;
+    let base_url = proj_options()
+        .map(|options| options.site.base_url.trim_end_matches('/').to_string())
+        .unwrap_or_default();
+    let resolve = |link: &str| {
+        let (_, slug, fragment) = local_page_link(link, &base_url)?;
+        let anchor = targets.get(slug)?;
+        Some(format!("#{}", fragment.unwrap_or(anchor)))
+    };
+
+    let mut content = toc(&chapters);
+    let mut sidebar = String::new();
+    chapters.iter().enumerate().for_each(|(index, chapter)| {
+        if index == 0 {
+            content.push_str(&chapter.content);
+        } else {
+            let _ = write!(
+                content,
+                "\n<section class=\"print-chapter\" id=\"{}\">\n<h2 class=\"print-chapter-title\">{}</h2>\n{}\n</section>",
+                escape_xml(&chapter.anchor),
+                chapter_title(chapter.article),
+                chapter.content
+            );
+        }
+        sidebar.push_str(&chapter.full_sidebar);
+    });
+    let content = rewrite_hrefs(&content, resolve);
+    let sidebar = rewrite_hrefs(&sidebar, resolve);
+
+    let mut html = pass_schema(
+        config,
+        article.schema,
+        article,
+        &content,
+        &sidebar,
+        global_data,
+    )?;
+    // Components and styles used by the sub chapters
+    chapters.iter().skip(1).for_each(|chapter| {
+        global_data.init_component_head(chapter.article, &mut html.head);
+        global_data.init_rewrite_head(chapter.article, &mut html.head);
+        global_data.init_article_head(chapter.article, &mut html.head);
+    });
+    let print_path = config
+        .output_print_path(&article.path, article.permalink().as_deref())
+        .map_err(|err| TypError::new_with(article.slug.clone(), vec![err]))?;
+    Ok((article.path.clone(), print_path, html))
+}
+
+fn chapter_title(article: &Article) -> String {
+    let title = article
+        .get_meta_contents()
+        .get(TITLE_KEY)
+        .map(|it| strip_tags(&it))
+        .unwrap_or_default();
+    if title.is_empty() {
+        escape_xml(&article.slug)
+    } else {
+        escape_xml(&title)
+    }
+}
+
+// Table of contents of all chapters, with the headings of each chapter
+fn toc(chapters: &[Chapter]) -> String {
+    fn list(chapters: &[Chapter], parent: Option<usize>, toc: &mut String) {
+        let children = chapters
+            .iter()
+            .enumerate()
+            .filter(|(_, chapter)| chapter.parent == parent)
+            .collect::<Vec<_>>();
+        if children.is_empty() {
+            return;
+        }
+        toc.push_str("<ol>\n");
+        children.into_iter().for_each(|(index, chapter)| {
+            let _ = write!(
+                toc,
+                "<li><a href=\"#{}\">{}</a>",
+                escape_xml(&chapter.anchor),
+                chapter_title(chapter.article)
+            );
+            let headings = sidebar_links(&chapter.full_sidebar);
+            if !headings.is_empty() {
+                toc.push_str("\n<ol>\n");
+                headings.iter().for_each(|(anchor, heading)| {
+                    let _ = writeln!(
+                        toc,
+                        "<li><a href=\"#{}\">{}</a></li>",
+                        escape_xml(anchor),
+                        escape_xml(heading)
+                    );
+                });
+                toc.push_str("</ol>\n");
+            }
+            list(chapters, Some(index), toc);
+            toc.push_str("</li>\n");
+        });
+        toc.push_str("</ol>\n");
+    }
+    let mut toc = String::from("<nav class=\"print-toc\">\n");
+    list(chapters, None, &mut toc);
+    toc.push_str("</nav>\n");
+    toc
+}
+
+// Rewrite the values of `href` attributes in place, the rest of the HTML is kept as it is
+fn rewrite_hrefs<F>(html: &str, resolve: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    const HREF: &str = "href=\"";
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(HREF) {
+        let (head, tail) = rest.split_at(start + HREF.len());
+        result.push_str(head);
+        let Some(end) = tail.find('"') else {
+            rest = tail;
+            break;
+        };
+        let value = &tail[..end];
+        match resolve(value) {
+            Some(link) => result.push_str(&link),
+            None => result.push_str(value),
+        }
+        rest = &tail[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrite_local_links() {
+        let targets: HashMap<&str, &str> = [("/a", "a"), ("/b/c", "b/c")].into_iter().collect();
+        let resolve = |link: &str| {
+            let (_, slug, fragment) = local_page_link(link, "https://example.com")?;
+            let anchor = targets.get(slug)?;
+            Some(format!("#{}", fragment.unwrap_or(anchor)))
+        };
+        let html = r#"<a href="/a">A</a><a href="https://example.com/b/c.html#h-1">C</a><a href="/d">D</a><a href="https://other.com/a">E</a>"#;
+        assert_eq!(
+            rewrite_hrefs(html, resolve),
+            r##"<a href="#a">A</a><a href="#h-1">C</a><a href="/d">D</a><a href="https://other.com/a">E</a>"##
+        );
+    }
+}
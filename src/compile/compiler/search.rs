@@ -0,0 +1,325 @@
+use crate::compile::registry::Key;
+use crate::compile::{compile_options, proj_options};
+use crate::config::TypsiteConfig;
+use crate::ir::article::Article;
+use crate::util::error::{log_err, log_err_or_ok};
+use crate::util::fs::write_into_file;
+use crate::util::html::{html_as_str, sidebar_links, strip_tags};
+use crate::walk_glob;
+use anyhow::Context;
+use glob::glob;
+use html5gum::{Token, Tokenizer};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::page_composer::PageCache;
+use super::{GeneratedFiles, PathBufs};
+
+const SEARCH_CACHE_DIR: &str = "search";
+const SEARCH_OUTPUT_DIR: &str = "search";
+// Files written by the last index, relative to the cache dir
+const OUTPUTS_CACHE_PATH: &str = "search-outputs.json";
+const SUMMARY_LENGTH: usize = 160;
+
+const TITLE_KEY: &str = "title";
+const TAXON_KEY: &str = "taxon";
+
+// Search document of an article, cached per article under `.cache/search`
+#[derive(Debug, Serialize, Deserialize)]
+struct SearchDoc {
+    url: String,
+    title: String,
+    taxon: String,
+    // (anchor, heading)
+    headings: Vec<(String, String)>,
+    text: String,
+}
+
+#[derive(Serialize)]
+struct DocEntry<'a> {
+    url: &'a str,
+    title: &'a str,
+    taxon: &'a str,
+    headings: &'a [(String, String)],
+    summary: String,
+}
+
+#[derive(Serialize)]
+struct IndexMeta {
+    shards: usize,
+    docs: usize,
+}
+
+pub struct SearchInput<'a, 'b> {
+    pub config: &'b TypsiteConfig<'a>,
+    pub cache_path: &'b Path,
+    pub articles: &'b HashMap<Key, Article<'a>>,
+    pub page_cache: &'b PageCache,
+    pub deleted: &'b PathBufs,
+    pub errors: &'b HashSet<&'b Path>,
+}
+
+// Refresh the documents of updated articles, then rebuild the index if any of them changed
+pub fn generate_search_index(input: SearchInput) -> GeneratedFiles {
+    let options = match proj_options() {
+        Ok(options) => options,
+        Err(_) => return Vec::new(),
+    };
+    if !options.search.enable {
+        return Vec::new();
+    }
+    let SearchInput {
+        config,
+        cache_path,
+        articles,
+        page_cache,
+        deleted,
+        errors,
+    } = input;
+    let cache_dir = cache_path.join(SEARCH_CACHE_DIR);
+    let doc_path = |slug: &str| cache_dir.join(format!("{}.json", slug.trim_start_matches('/')));
+
+    let removed = deleted
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(errors.iter().copied())
+        .filter_map(|path| config.path_to_slug(path).ok())
+        .filter(|slug| std::fs::remove_file(doc_path(slug)).is_ok())
+        .count();
+
+    let pretty_url = compile_options()
+        .map(|options| options.pretty_url)
+        .unwrap_or(true);
+    // Only the composed pages are re-indexed
+    let updated = page_cache
+        .iter()
+        .filter_map(|(slug, (content, full_sidebar, _))| {
+            let article = articles.get(slug)?;
+            (!errors.contains(article.path.as_ref())).then_some((article, content, full_sidebar))
+        })
+        .map(|(article, content, full_sidebar)| {
+            let doc = search_doc(article, &content.join(""), &full_sidebar.join(""), pretty_url);
+            serde_json::to_string(&doc)
+                .context("Failed to serialize search document")
+                .and_then(|json| write_into_file(doc_path(&article.slug), &json, "search document"))
+        })
+        .map(log_err)
+        .count();
+
+    if removed == 0 && updated == 0 {
+        return Vec::new();
+    }
+    let docs = walk_glob!("{}/**/*.json", cache_dir.display())
+        .map(|path| {
+            std::fs::read_to_string(&path)
+                .context("Failed to read search document")
+                .and_then(|json| {
+                    serde_json::from_str::<SearchDoc>(&json)
+                        .with_context(|| format!("Failed to parse search document {path:?}"))
+                })
+        })
+        .filter_map(log_err_or_ok)
+        .collect::<Vec<_>>();
+    build_index(docs, options.search.shards.max(1))
+}
+
+// Shards of the previous index which are not written anymore, e.g. after `search.shards` is reduced
+pub fn stale_search_shards(cache_path: &Path, generated: &GeneratedFiles) -> Vec<PathBuf> {
+    if generated.is_empty() {
+        return Vec::new();
+    }
+    let outputs_path = cache_path.join(OUTPUTS_CACHE_PATH);
+    let previous = std::fs::read_to_string(&outputs_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Vec<PathBuf>>(&json).ok())
+        .unwrap_or_default();
+    let outputs = generated
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    let written = serde_json::to_string(&outputs)
+        .context("Failed to serialize search outputs")
+        .and_then(|json| write_into_file(outputs_path, &json, "search outputs"));
+    log_err(written);
+    previous
+        .into_iter()
+        .filter(|path| !outputs.contains(path))
+        .collect()
+}
+
+fn search_doc(article: &Article, content: &str, full_sidebar: &str, pretty_url: bool) -> SearchDoc {
+    let contents = article.get_meta_contents();
+    let get = |key: &str| {
+        contents
+            .get(key)
+            .map(|it| strip_tags(&it))
+            .unwrap_or_default()
+    };
+    let slug = contents.public_slug();
+    let url = if pretty_url {
+        slug
+    } else {
+        format!("{slug}.html")
+    };
+    SearchDoc {
+        url,
+        title: get(TITLE_KEY),
+        taxon: get(TAXON_KEY),
+        headings: sidebar_links(full_sidebar),
+        text: extract_text(content),
+    }
+}
+
+// Plain text of the composed body, scripts and styles excluded
+fn extract_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut skip = 0usize;
+    for token in Tokenizer::new(html).flatten() {
+        match token {
+            Token::StartTag(tag) if matches!(tag.name.as_slice(), b"script" | b"style") => {
+                if !tag.self_closing {
+                    skip += 1;
+                }
+            }
+            Token::EndTag(tag) if matches!(tag.name.as_slice(), b"script" | b"style") => {
+                skip = skip.saturating_sub(1);
+            }
+            Token::String(string) if skip == 0 => {
+                text.push_str(&html_as_str(&string));
+                text.push(' ');
+            }
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn build_index(mut docs: Vec<SearchDoc>, shards: usize) -> GeneratedFiles {
+    docs.sort_by(|a, b| a.url.cmp(&b.url));
+    // token -> doc id -> term frequency
+    let mut index: Vec<BTreeMap<String, BTreeMap<usize, u32>>> = vec![BTreeMap::new(); shards];
+    docs.iter().enumerate().for_each(|(id, doc)| {
+        let headings = doc
+            .headings
+            .iter()
+            .map(|(_, heading)| heading.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        // Titles and headings weigh more than the body
+        let weighted = [
+            (doc.title.as_str(), 5),
+            (doc.taxon.as_str(), 3),
+            (headings.as_str(), 3),
+            (doc.text.as_str(), 1),
+        ];
+        for (text, weight) in weighted {
+            for token in tokenize(text) {
+                let shard = shard_of(&token, shards);
+                *index[shard]
+                    .entry(token)
+                    .or_default()
+                    .entry(id)
+                    .or_default() += weight;
+            }
+        }
+    });
+    let entries = docs
+        .iter()
+        .map(|doc| DocEntry {
+            url: &doc.url,
+            title: &doc.title,
+            taxon: &doc.taxon,
+            headings: &doc.headings,
+            summary: doc.text.chars().take(SUMMARY_LENGTH).collect(),
+        })
+        .collect::<Vec<_>>();
+    let meta = IndexMeta {
+        shards,
+        docs: docs.len(),
+    };
+    let dir = PathBuf::from(SEARCH_OUTPUT_DIR);
+    let mut files = Vec::new();
+    if let Some(json) = log_err_or_ok(serde_json::to_string(&meta).context("Failed to serialize search meta")) {
+        files.push((dir.join("meta.json"), json));
+    }
+    if let Some(json) = log_err_or_ok(serde_json::to_string(&entries).context("Failed to serialize search docs")) {
+        files.push((dir.join("docs.json"), json));
+    }
+    index.into_iter().enumerate().for_each(|(shard, tokens)| {
+        let result = serde_json::to_string(&tokens).context("Failed to serialize search index");
+        if let Some(json) = log_err_or_ok(result) {
+            files.push((dir.join(format!("index-{shard}.json")), json));
+        }
+    });
+    files
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana & Katakana
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF   // Hangul
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // CJK Extension B ~
+    )
+}
+
+// Words are split by non-alphanumeric chars,
+// CJK runs have no spaces, so they are indexed as single chars and bigrams.
+// The client script tokenizes queries in the same way.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk_run: Vec<char> = Vec::new();
+    let flush_cjk = |run: &mut Vec<char>, tokens: &mut Vec<String>| {
+        run.iter().for_each(|c| tokens.push(c.to_string()));
+        run.windows(2)
+            .for_each(|pair| tokens.push(pair.iter().collect()));
+        run.clear();
+    };
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            cjk_run.push(c);
+        } else if c.is_alphanumeric() {
+            flush_cjk(&mut cjk_run, &mut tokens);
+            word.push(c);
+        } else {
+            flush_cjk(&mut cjk_run, &mut tokens);
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+        }
+    }
+    flush_cjk(&mut cjk_run, &mut tokens);
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+// FNV-1a over the UTF-8 bytes, the same as the client script
+fn shard_of(token: &str, shards: usize) -> usize {
+    let hash = token.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    hash as usize % shards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_mixed_text() {
+        assert_eq!(
+            tokenize("Hello, World! 概率论 v2"),
+            vec!["hello", "world", "概", "率", "论", "概率", "率论", "v2"]
+        );
+        assert_eq!(tokenize("typst排版"), vec!["typst", "排", "版", "排版"]);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::compile::registry::Key;
+use crate::compile::{compile_options, proj_options};
+use crate::ir::article::Article;
+use crate::util::date::Date;
+use crate::util::error::log_err;
+use crate::util::fs::write_into_file;
+use crate::util::html::{escape_xml, strip_tags};
+use anyhow::Context;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+use super::GeneratedFiles;
+
+const SITEMAP_PATH: &str = "sitemap.xml";
+// Shards written by the last sitemap index, relative to the cache dir
+const SHARDS_CACHE_PATH: &str = "sitemap-shards.json";
+
+static NO_BASE_URL_WARNING: Once = Once::new();
+
+// Regenerate sitemap.xml (or a sitemap index with its shards) if the article set is changed
+pub fn generate_sitemap(
+    articles: &HashMap<Key, Article<'_>>,
+    error_paths: &HashSet<&Path>,
+    changed: bool,
+) -> GeneratedFiles {
+    let options = match proj_options() {
+        Ok(options) => options,
+        Err(_) => return Vec::new(),
+    };
+    let sitemap = &options.sitemap;
+    if !sitemap.enable || !changed {
+        return Vec::new();
+    }
+    let site = &options.site;
+    // Sitemaps need absolute URLs, skipped until `site.base_url` is set
+    if site.base_url.is_empty() {
+        NO_BASE_URL_WARNING.call_once(|| {
+            eprintln!("[WARN] Sitemap is skipped, as `site.base_url` is not set");
+        });
+        return Vec::new();
+    }
+    let pretty_url = compile_options()
+        .map(|options| options.pretty_url)
+        .unwrap_or(true);
+    let mut urls = articles
+        .values()
+        .filter(|article| !error_paths.contains(article.path.as_ref()))
+        .filter_map(|article| {
+            let contents = article.get_meta_contents();
+            let excluded = contents
+                .get(&sitemap.exclude_key)
+                .map(|it| strip_tags(&it).eq_ignore_ascii_case("false"))
+                .unwrap_or(false);
+            if excluded {
+                return None;
+            }
+            let lastmod = contents
+                .get(&sitemap.lastmod_key)
+                .and_then(|date| Date::parse(&strip_tags(&date)))
+                .or_else(|| {
+                    let modified = fs::metadata(&article.path).ok()?.modified().ok()?;
+                    Date::from_system_time(modified)
+                });
+            let url = site.page_url(&contents.public_slug(), pretty_url);
+            Some((url, lastmod))
+        })
+        .collect::<Vec<_>>();
+    urls.sort();
+
+    let max_urls = sitemap.max_urls.max(1);
+    if urls.len() <= max_urls {
+        return vec![(PathBuf::from(SITEMAP_PATH), urlset(&urls))];
+    }
+    let mut files = Vec::new();
+    let mut index = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    urls.chunks(max_urls).enumerate().for_each(|(i, chunk)| {
+        let path = format!("sitemap-{}.xml", i + 1);
+        let lastmod = chunk.iter().filter_map(|(_, lastmod)| *lastmod).max();
+        let _ = write!(
+            index,
+            "  <sitemap>\n    <loc>{}</loc>\n",
+            escape_xml(&site.url(&path))
+        );
+        if let Some(lastmod) = lastmod {
+            let _ = writeln!(index, "    <lastmod>{}</lastmod>", lastmod.rfc3339());
+        }
+        index.push_str("  </sitemap>\n");
+        files.push((PathBuf::from(path), urlset(chunk)));
+    });
+    index.push_str("</sitemapindex>\n");
+    files.push((PathBuf::from(SITEMAP_PATH), index));
+    files
+}
+
+// Shards of the previous sitemap index, which are not regenerated,
+// only the shards written by typsite are tracked and removed
+pub fn stale_sitemap_shards(cache_path: &Path, generated: &GeneratedFiles) -> Vec<PathBuf> {
+    if generated.is_empty() {
+        return Vec::new();
+    }
+    let shards_path = cache_path.join(SHARDS_CACHE_PATH);
+    let previous = fs::read_to_string(&shards_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Vec<PathBuf>>(&json).ok())
+        .unwrap_or_default();
+    let shards = generated
+        .iter()
+        .map(|(path, _)| path.clone())
+        .filter(|path| path.as_path() != Path::new(SITEMAP_PATH))
+        .collect::<Vec<_>>();
+    let written = serde_json::to_string(&shards)
+        .context("Failed to serialize sitemap shards")
+        .and_then(|json| write_into_file(shards_path, &json, "sitemap shards"));
+    log_err(written);
+    previous
+        .into_iter()
+        .filter(|path| !shards.contains(path))
+        .collect()
+}
+
+fn urlset(urls: &[(String, Option<Date>)]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    urls.iter().for_each(|(url, lastmod)| {
+        let _ = write!(xml, "  <url>\n    <loc>{}</loc>\n", escape_xml(url));
+        if let Some(lastmod) = lastmod {
+            let _ = writeln!(xml, "    <lastmod>{}</lastmod>", lastmod.rfc3339());
+        }
+        xml.push_str("  </url>\n");
+    });
+    xml.push_str("</urlset>\n");
+    xml
+}
@@ -0,0 +1,136 @@
+use crate::compile::registry::Key;
+use crate::config::TypsiteConfig;
+use crate::ir::article::Article;
+use crate::util::error::{TypsiteError, log_err_or_ok};
+use crate::util::fs::{create_all_parent_dir, write_into_file};
+use crate::util::html::strip_tags;
+use anyhow::{Context, Error};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use super::CopiedFiles;
+
+const SOCIAL_CARD_CACHE_DIR: &str = "og";
+// Metacontents passed into the template by `sys.inputs`
+const CARD_KEYS: [&str; 4] = ["title", "taxon", "author", "date"];
+
+pub struct SocialCardInput<'a, 'b> {
+    pub typst: &'b str,
+    pub config: &'b TypsiteConfig<'a>,
+    pub config_path: &'b Path,
+    pub cache_path: &'b Path,
+    pub output_path: &'b Path,
+    pub articles: &'b HashMap<Key, Article<'a>>,
+    pub errors: &'b HashSet<&'b Path>,
+}
+
+// Render the social card of each article by `.typsite/og.typ`,
+// only if its metacontents or the template are changed since the last time
+pub fn generate_social_cards(input: SocialCardInput) -> CopiedFiles {
+    let SocialCardInput {
+        typst,
+        config,
+        config_path,
+        cache_path,
+        output_path,
+        articles,
+        errors,
+    } = input;
+    let Some(template) = config.social_card.as_ref() else {
+        return Vec::new();
+    };
+    let template_content = match fs::read(template) {
+        Ok(content) => content,
+        Err(err) => {
+            eprintln!("[WARN] Failed to read social card template {template:?}: {err}");
+            return Vec::new();
+        }
+    };
+    let cache_dir = cache_path.join(SOCIAL_CARD_CACHE_DIR);
+    articles
+        .par_iter()
+        .filter(|(_, article)| !errors.contains(article.path.as_ref()))
+        .filter_map(|(slug, article)| {
+            let contents = article.get_meta_contents();
+            let inputs = CARD_KEYS
+                .iter()
+                .map(|key| {
+                    let value = contents
+                        .get(key)
+                        .map(|it| strip_tags(&it))
+                        .unwrap_or_default();
+                    (*key, value)
+                })
+                .collect::<Vec<_>>();
+            let hash = card_hash(&template_content, &inputs);
+
+            let card_path = cache_dir.join(format!("{}.png", slug.trim_start_matches('/')));
+            let hash_path = card_path.with_extension("hash");
+            let output_card = config
+                .output_card_path(&article.path, article.permalink().as_deref())
+                .ok()?;
+            let unchanged = fs::read_to_string(&hash_path).is_ok_and(|old| old == hash)
+                && card_path.exists();
+            if unchanged && output_path.join(&output_card).exists() {
+                return None;
+            }
+            if !unchanged {
+                let result = compile_card(typst, config_path, template, &inputs, &card_path)
+                    .with_context(|| format!("Failed to render social card of {slug}"))
+                    .and_then(|_| write_into_file(&hash_path, &hash, "social card hash"));
+                log_err_or_ok(result)?;
+            }
+            Some((card_path, output_card))
+        })
+        .collect()
+}
+
+fn card_hash(template: &[u8], inputs: &[(&str, String)]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(template);
+    inputs.iter().for_each(|(key, value)| {
+        hasher.update(key.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(value.as_bytes());
+        hasher.update(b"\0");
+    });
+    hasher.finalize().to_hex().to_string()
+}
+
+fn compile_card(
+    typst: &str,
+    config_path: &Path,
+    template: &Path,
+    inputs: &[(&str, String)],
+    output: &Path,
+) -> anyhow::Result<()> {
+    create_all_parent_dir(output)?;
+    let font_path = config_path.join("assets/fonts");
+    let mut command = Command::new(typst);
+    command
+        .arg("c")
+        .arg(template)
+        .arg("--root")
+        .arg(config_path)
+        .arg("-f=png")
+        .arg("--pages")
+        .arg("1")
+        .arg("--font-path")
+        .arg(font_path);
+    inputs.iter().for_each(|(key, value)| {
+        command.arg("--input").arg(format!("{key}={value}"));
+    });
+    let output = command
+        .arg(output)
+        .output()
+        .with_context(|| format!("Typst compile to PNG failed: {}", template.display()))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(Error::new(TypsiteError::Typst(stderr.to_string())))
+    }
+}
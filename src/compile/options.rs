@@ -1,13 +1,15 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub struct CompileOptions {
     pub watch: bool,
     pub short_slug: bool,
     pub pretty_url: bool,
+    // Root of the caches, e.g. the outputs of `process` rewrites
+    pub cache_path: PathBuf,
 }
 
 pub const OPTIONS_PATH: &str = "options.toml";
@@ -17,6 +19,28 @@ pub struct ProjOptions {
     #[serde(deserialize_with = "lib_paths::deserialize_lib_paths")]
     pub typst_lib: TypstLib,
     pub code_fallback_style: CodeFallbackStyle,
+    #[serde(default)]
+    pub code_highlight: CodeHighlight,
+    #[serde(default)]
+    pub site: Site,
+    #[serde(default)]
+    pub feeds: Vec<feed::Feed>,
+    #[serde(default)]
+    pub sitemap: sitemap::Sitemap,
+    #[serde(default)]
+    pub search: search::Search,
+    #[serde(default)]
+    pub pdf: pdf::Pdf,
+    #[serde(default)]
+    pub print: print::Print,
+    #[serde(default)]
+    pub graph: graph::GraphExport,
+    #[serde(default)]
+    pub local_graph: local_graph::LocalGraph,
+    #[serde(default)]
+    pub check: check::Check,
+    #[serde(default)]
+    pub relations: Vec<relation::Relation>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -38,11 +62,42 @@ impl ProjOptions {
         Ok(options)
     }
 }
+#[derive(Debug, Default, Deserialize)]
+pub struct Site {
+    // e.g. "https://example.com", used to form absolute URLs
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub title: String,
+}
+
+impl Site {
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+
+    // Absolute URL of a page by its public slug
+    pub fn page_url(&self, slug: &str, pretty_url: bool) -> String {
+        if pretty_url {
+            self.url(slug)
+        } else {
+            self.url(&format!("{slug}.html"))
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CodeFallbackStyle {
     pub dark: String,
     pub light: String,
 }
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CodeHighlight {
+    // Emit classes instead of inline colors, styled by the generated `rewrite/code_themes.css`
+    #[serde(default)]
+    pub classed: bool,
+}
 pub mod metadata {
     use crate::{
         compile::{proj_options, registry::Key},
@@ -134,6 +189,274 @@ pub mod metadata {
     }
 }
 
+pub mod feed {
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum FeedFormat {
+        Atom,
+        Rss,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Feed {
+        // Output path of the feed, e.g. "feed.xml"
+        pub path: String,
+        #[serde(default = "default_format")]
+        pub format: FeedFormat,
+        #[serde(default)]
+        pub title: Option<String>,
+        // Only articles whose slug starts with the prefix
+        #[serde(default)]
+        pub prefix: Option<String>,
+        // Only articles with the taxon
+        #[serde(default)]
+        pub taxon: Option<String>,
+        #[serde(default = "default_limit")]
+        pub limit: usize,
+        #[serde(default = "default_date_key")]
+        pub date_key: String,
+        #[serde(default = "default_summary_key")]
+        pub summary_key: String,
+        // Take the composed page body as the content of items
+        #[serde(default)]
+        pub full_content: bool,
+    }
+
+    pub fn default_format() -> FeedFormat {
+        FeedFormat::Atom
+    }
+    pub fn default_limit() -> usize {
+        20
+    }
+    pub fn default_date_key() -> String {
+        "date".to_string()
+    }
+    pub fn default_summary_key() -> String {
+        "summary".to_string()
+    }
+}
+
+pub mod sitemap {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Sitemap {
+        #[serde(default = "default_enable")]
+        pub enable: bool,
+        // metacontent used as `lastmod`, fallback to the modification time of the source file
+        #[serde(default = "default_lastmod_key")]
+        pub lastmod_key: String,
+        // articles with this metacontent set to "false" are excluded
+        #[serde(default = "default_exclude_key")]
+        pub exclude_key: String,
+        // split into a sitemap index if exceeded
+        #[serde(default = "default_max_urls")]
+        pub max_urls: usize,
+    }
+
+    impl Default for Sitemap {
+        fn default() -> Self {
+            Self {
+                enable: default_enable(),
+                lastmod_key: default_lastmod_key(),
+                exclude_key: default_exclude_key(),
+                max_urls: default_max_urls(),
+            }
+        }
+    }
+
+    pub fn default_enable() -> bool {
+        true
+    }
+    pub fn default_lastmod_key() -> String {
+        "date".to_string()
+    }
+    pub fn default_exclude_key() -> String {
+        "sitemap".to_string()
+    }
+    pub fn default_max_urls() -> usize {
+        50000
+    }
+}
+
+pub mod search {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Search {
+        #[serde(default = "default_enable")]
+        pub enable: bool,
+        // number of inverted index shards, the client only fetches the shards it needs
+        #[serde(default = "default_shards")]
+        pub shards: usize,
+    }
+
+    impl Default for Search {
+        fn default() -> Self {
+            Self {
+                enable: default_enable(),
+                shards: default_shards(),
+            }
+        }
+    }
+
+    pub fn default_enable() -> bool {
+        true
+    }
+    pub fn default_shards() -> usize {
+        16
+    }
+}
+
+pub mod pdf {
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Deserialize)]
+    pub struct Pdf {
+        // export all articles as PDF, otherwise only the ones with `pdf-export` metaoption
+        #[serde(default)]
+        pub enable: bool,
+    }
+}
+
+pub mod print {
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Deserialize)]
+    pub struct Print {
+        // generate a print view of each article, flattening its embedded and child articles
+        #[serde(default)]
+        pub enable: bool,
+    }
+}
+
+pub mod graph {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Deserialize, clap::ValueEnum)]
+    #[serde(rename_all = "lowercase")]
+    pub enum GraphFormat {
+        Json,
+        Dot,
+    }
+
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        Serialize,
+        Deserialize,
+        clap::ValueEnum,
+    )]
+    #[serde(rename_all = "lowercase")]
+    pub enum EdgeKind {
+        Parent,
+        Child,
+        Reference,
+        Backlink,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct GraphExport {
+        // dump the site graph into the output on each build
+        #[serde(default)]
+        pub enable: bool,
+        #[serde(default = "default_path")]
+        pub path: String,
+        #[serde(default = "default_format")]
+        pub format: GraphFormat,
+        // Only articles whose slug starts with the prefix
+        #[serde(default)]
+        pub prefix: Option<String>,
+        #[serde(default = "default_edges")]
+        pub edges: Vec<EdgeKind>,
+    }
+
+    impl Default for GraphExport {
+        fn default() -> Self {
+            Self {
+                enable: false,
+                path: default_path(),
+                format: default_format(),
+                prefix: None,
+                edges: default_edges(),
+            }
+        }
+    }
+
+    pub fn default_path() -> String {
+        "graph.json".to_string()
+    }
+    pub fn default_format() -> GraphFormat {
+        GraphFormat::Json
+    }
+    pub fn default_edges() -> Vec<EdgeKind> {
+        vec![
+            EdgeKind::Parent,
+            EdgeKind::Child,
+            EdgeKind::Reference,
+            EdgeKind::Backlink,
+        ]
+    }
+}
+
+pub mod local_graph {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct LocalGraph {
+        // write the neighbourhood of each article next to its page, for `<localgraph />`
+        #[serde(default)]
+        pub enable: bool,
+        // hops from the article
+        #[serde(default = "default_depth")]
+        pub depth: usize,
+    }
+
+    impl Default for LocalGraph {
+        fn default() -> Self {
+            Self {
+                enable: false,
+                depth: default_depth(),
+            }
+        }
+    }
+
+    pub fn default_depth() -> usize {
+        1
+    }
+}
+
+pub mod check {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Check {
+        // report orphan articles and the ones not reachable from the default parent after each build
+        #[serde(default = "default_orphans")]
+        pub orphans: bool,
+    }
+
+    impl Default for Check {
+        fn default() -> Self {
+            Self {
+                orphans: default_orphans(),
+            }
+        }
+    }
+
+    pub fn default_orphans() -> bool {
+        true
+    }
+}
+
 mod lib_paths {
 
     use std::collections::HashSet;
@@ -166,3 +489,37 @@ mod lib_paths {
         Ok(TypstLib { files, dirs })
     }
 }
+
+pub mod relation {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Relation {
+        // Metagraph kind declaring the relation, e.g. "prerequisite"
+        pub name: String,
+        // Name of the relation seen from the other article, e.g. "required-by",
+        // the same as the name for symmetric relations
+        #[serde(default)]
+        pub inverse: Option<String>,
+    }
+
+    impl Relation {
+        // The relation declared by a metagraph kind, and if the kind is the inverse name
+        pub fn find<'r>(relations: &'r [Relation], kind: &str) -> Option<(&'r Relation, bool)> {
+            relations.iter().find_map(|relation| {
+                if relation.name == kind {
+                    Some((relation, false))
+                } else if relation.inverse.as_deref() == Some(kind) {
+                    Some((relation, true))
+                } else {
+                    None
+                }
+            })
+        }
+
+        // Names rendered in the footer, the inverse included
+        pub fn names(&self) -> impl Iterator<Item = &str> {
+            std::iter::once(self.name.as_str()).chain(self.inverse.as_deref())
+        }
+    }
+}
@@ -1,6 +1,7 @@
-use crate::config::TypsiteConfig;
+use crate::config::{TypsiteConfig, check_permalink};
 use crate::util::error::log_err;
-use anyhow::{Context, Result};
+use crate::util::str::did_you_mean;
+use anyhow::{Context, Result, anyhow};
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
@@ -14,6 +15,10 @@ pub type KeyMap<V> = HashMap<String, V>;
 pub struct KeyRegistry {
     known_articles: KeyMap<Key>,
     article_paths: KeyMap<SlugPath>,
+    // permalink / alias -> slug
+    permalinks: KeyMap<Key>,
+    // slug -> its permalink and aliases
+    article_permalinks: KeyMap<Vec<String>>,
 }
 
 impl KeyRegistry {
@@ -21,6 +26,8 @@ impl KeyRegistry {
         Self {
             known_articles: KeyMap::new(),
             article_paths: KeyMap::new(),
+            permalinks: KeyMap::new(),
+            article_permalinks: KeyMap::new(),
         }
     }
 
@@ -37,6 +44,47 @@ impl KeyRegistry {
     pub fn remove_slug(&mut self, slug: &str) {
         self.known_articles.remove(slug);
         self.article_paths.remove(slug);
+        self.remove_permalinks(slug);
+    }
+
+    // Register the permalink and aliases of an article,
+    // both of them could be used to refer to the article instead of its path
+    pub fn register_permalinks(
+        &mut self,
+        slug: &Key,
+        permalink: Option<String>,
+        aliases: Vec<String>,
+    ) -> Result<()> {
+        self.remove_permalinks(slug);
+        let links: Vec<String> = permalink.into_iter().chain(aliases).collect();
+        for link in links.iter() {
+            check_permalink(link)?;
+            let claimed = self
+                .permalinks
+                .get(link)
+                .or_else(|| self.known_articles.get(link))
+                .filter(|it| it.as_ref() != slug.as_ref());
+            if let Some(claimed) = claimed {
+                return Err(anyhow!(
+                    "Duplicate permalink {link}: claimed by both {claimed} and {slug}"
+                ));
+            }
+        }
+        links.iter().for_each(|link| {
+            self.permalinks.insert(link.clone(), slug.clone());
+        });
+        if !links.is_empty() {
+            self.article_permalinks.insert(slug.to_string(), links);
+        }
+        Ok(())
+    }
+
+    pub fn remove_permalinks(&mut self, slug: &str) {
+        if let Some(links) = self.article_permalinks.remove(slug) {
+            links.iter().for_each(|link| {
+                self.permalinks.remove(link);
+            });
+        }
     }
 
     pub fn register_paths<I, P>(&mut self, config: &TypsiteConfig, paths: I)
@@ -73,8 +121,12 @@ impl KeyRegistry {
         Ok(slug_with_path)
     }
 
+    // Resolve a slug by either its path or its permalink / aliases
     pub fn slug(&self, slug: &str) -> Option<Key> {
-        self.known_articles.get(slug).cloned()
+        self.known_articles
+            .get(slug)
+            .or_else(|| self.permalinks.get(slug))
+            .cloned()
     }
 
     pub fn path(&self, slug: &str) -> Option<SlugPath> {
@@ -82,9 +134,46 @@ impl KeyRegistry {
     }
 
     pub fn know(&self, slug: String, tag: &str, from: &str) -> Result<Key> {
-        self.known_articles
-            .get(&slug)
-            .cloned()
-            .with_context(|| format!("{tag} not found: {slug} in {from}"))
+        self.slug(&slug).with_context(|| {
+            let suggestion = self.did_you_mean(&slug);
+            format!("{tag} not found: {slug} in {from}{suggestion}")
+        })
+    }
+
+    // Suggest the closest known slugs & permalinks of an unresolved slug
+    pub fn did_you_mean(&self, slug: &str) -> String {
+        let candidates = self
+            .known_articles
+            .keys()
+            .chain(self.permalinks.keys())
+            .map(String::as_str);
+        did_you_mean(slug, candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_and_duplicate_permalinks() {
+        let mut registry = KeyRegistry::new();
+        let a = registry.register_slug("/a".to_string());
+        let b = registry.register_slug("/b".to_string());
+        registry
+            .register_permalinks(&a, Some("/post".to_string()), vec!["/old".to_string()])
+            .unwrap();
+        assert_eq!(registry.slug("/post"), Some(a.clone()));
+        assert_eq!(registry.slug("/old"), Some(a.clone()));
+        assert!(registry.register_permalinks(&b, Some("/post".to_string()), vec![]).is_err());
+        assert!(registry.register_permalinks(&b, Some("/a".to_string()), vec![]).is_err());
+        assert!(registry.register_permalinks(&b, Some("/../etc".to_string()), vec![]).is_err());
+        assert!(registry.register_permalinks(&b, None, vec!["/a//b".to_string()]).is_err());
+        assert!(registry.register_permalinks(&b, Some("/x/./y".to_string()), vec![]).is_err());
+        // Re-registering replaces the old permalinks
+        registry.register_permalinks(&a, Some("/new".to_string()), vec![]).unwrap();
+        assert_eq!(registry.slug("/post"), None);
+        registry.register_permalinks(&b, Some("/post".to_string()), vec![]).unwrap();
+        assert_eq!(registry.know("/post".to_string(), "Test", "").unwrap(), b);
     }
 }
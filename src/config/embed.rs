@@ -1,27 +1,114 @@
-use crate::config::{EMBED_PATH, EMBED_TITLE_PATH};
-use crate::util::html::{Html, HtmlWithElem};
+use crate::config::{EMBED_PATH, EMBED_TITLE_PATH, EMBEDS_DIR};
+use crate::ir::embed::variable::EmbedVariable;
+use crate::util::html::{Html, HtmlWithElem, take_tags};
+use crate::util::path::{file_ext, file_stem};
 use crate::util::str::SectionElem;
+use anyhow::Context;
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
+const VARIABLE_KEY: &str = "variable";
+
 pub struct EmbedConfig {
-    pub embed_path: Arc<Path>,
+    // The default template, `components/embed.html`
+    pub embed: EmbedTemplate,
     pub embed_title_path: Arc<Path>,
-    pub embed: HtmlWithElem<SectionElem>,
     pub embed_title: Html,
+    // Templates chosen by `template` of embeds, `components/embeds/<name>.html`
+    pub templates: HashMap<String, EmbedTemplate>,
+}
+
+pub struct EmbedTemplate {
+    pub path: Arc<Path>,
+    pub html: HtmlWithElem<SectionElem>,
+    // Variables declared by `<variable />` in the head of the template
+    pub variables: Vec<EmbedVariable>,
+}
+
+impl EmbedTemplate {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut html = HtmlWithElem::load(path)?;
+        let (head, variables) = take_variables(&html.head)
+            .with_context(|| format!("Invalid variable declaration in {path:?}"))?;
+        html.head = head;
+        Ok(Self {
+            path: Arc::from(path),
+            html,
+            variables,
+        })
+    }
 }
 
 impl EmbedConfig {
     pub fn load(config: &Path) -> anyhow::Result<Self> {
-        let embed_path = Arc::from(config.join(EMBED_PATH));
+        let embed = EmbedTemplate::load(&config.join(EMBED_PATH))?;
         let embed_title_path = Arc::from(config.join(EMBED_TITLE_PATH));
-        let embed = HtmlWithElem::load(&embed_path)?;
         let embed_title = Html::load(&embed_title_path)?;
+        let templates = Self::load_templates(config)?;
         Ok(Self {
-            embed_path,
-            embed_title_path,
             embed,
+            embed_title_path,
             embed_title,
+            templates,
         })
     }
+
+    fn load_templates(config: &Path) -> anyhow::Result<HashMap<String, EmbedTemplate>> {
+        let dir = config.join(EMBEDS_DIR);
+        if !dir.is_dir() {
+            return Ok(HashMap::new());
+        }
+        let mut templates = HashMap::new();
+        for entry in dir.read_dir()? {
+            let path = entry?.path();
+            if file_ext(&path).as_deref() != Some("html") {
+                continue;
+            }
+            if let Some(name) = file_stem(&path) {
+                templates.insert(name.to_string(), EmbedTemplate::load(&path)?);
+            }
+        }
+        Ok(templates)
+    }
+
+    pub fn has_template(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
+    }
+
+    // The chosen template, or the default one
+    pub fn template(&self, name: Option<&str>) -> &EmbedTemplate {
+        name.and_then(|name| self.templates.get(name))
+            .unwrap_or(&self.embed)
+    }
+}
+
+// Split the variable declarations out of the head
+fn take_variables(head: &str) -> anyhow::Result<(String, Vec<EmbedVariable>)> {
+    let (rest, tags) = take_tags(head, VARIABLE_KEY)?;
+    let variables = tags
+        .into_iter()
+        .map(|attrs| {
+            let name = attrs.expect("name")?.to_string();
+            let kind = attrs.get("type").unwrap_or("string".into());
+            let default = attrs.get("default").map(|it| it.to_string());
+            EmbedVariable::new(name, &kind, default)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok((rest, variables))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_in_head() {
+        let head = r#"<link href="/embed.css"><variable name="show-metadata" type="bool" default="true" />"#;
+        let (head, variables) = take_variables(head).unwrap();
+        assert_eq!(head, r#"<link href="/embed.css">"#);
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables[0].default.as_deref(), Some("true"));
+        assert!(take_variables(r#"<variable name="x" type="date" />"#).is_err());
+    }
 }
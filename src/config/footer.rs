@@ -1,13 +1,24 @@
-use crate::config::{FOOTER_BACKLINKS_PATH, FOOTER_PATH, HtmlConfig};
+use crate::compile::proj_options;
+use crate::config::{
+    FOOTER_BACKLINKS_PATH, FOOTER_CITATION_PATH, FOOTER_DIR, FOOTER_PATH, FOOTER_REFERENCES_PATH,
+    HtmlConfig,
+};
+use std::collections::HashMap;
 use std::path::Path;
 
 pub const REFERENCES_KEY: &str = "{references}";
 pub const BACKLINKS_KEY: &str = "{backlinks}";
+// Left by `<citations />` in the backlink schema, replaced by the citations of each cited article
+pub const CITATIONS_MARKER: &str = "<!--typsite-citations-->";
 
 pub struct FooterConfig {
     pub footer: HtmlConfig,
     pub backlinks: HtmlConfig,
     pub references: HtmlConfig,
+    // Context of each citation listed by `<citations />` in the backlink schema
+    pub citation: HtmlConfig,
+    // Components of custom relations, `components/footer/<name>.html`
+    pub relations: HashMap<String, HtmlConfig>,
 }
 
 impl FooterConfig {
@@ -15,10 +26,48 @@ impl FooterConfig {
         let footer = HtmlConfig::load(config, FOOTER_PATH)?;
         let backlinks = HtmlConfig::load(config, FOOTER_BACKLINKS_PATH)?;
         let references = HtmlConfig::load(config, FOOTER_BACKLINKS_PATH)?;
+        let citation = HtmlConfig::load_optional(config, FOOTER_CITATION_PATH)?;
+        let relations = Self::load_relations(config)?;
         Ok(Self {
             footer,
             backlinks,
             references,
+            citation,
+            relations,
         })
     }
+
+    // Components of the relations declared in the options, the other footer files are not relations
+    fn load_relations(config: &Path) -> anyhow::Result<HashMap<String, HtmlConfig>> {
+        let names = proj_options()
+            .map(|options| {
+                options
+                    .relations
+                    .iter()
+                    .flat_map(|relation| relation.names())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let mut relations = HashMap::new();
+        for name in names {
+            let path = format!("{FOOTER_DIR}{name}.html");
+            if !config.join(&path).is_file()
+                || matches!(
+                    path.as_str(),
+                    FOOTER_BACKLINKS_PATH | FOOTER_REFERENCES_PATH | FOOTER_CITATION_PATH
+                )
+            {
+                continue;
+            }
+            let component = HtmlConfig::load(config, &path)?;
+            relations.insert(name, component);
+        }
+        Ok(relations)
+    }
+
+    // Key of a custom relation in footer.html
+    pub fn relation_key(name: &str) -> String {
+        format!("{{{name}}}")
+    }
 }
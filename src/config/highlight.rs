@@ -11,7 +11,7 @@ use std::fmt::{Display, Formatter};
 use std::fs;
 use std::path::Path;
 use std::sync::{Arc, OnceLock};
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{collections::{BTreeMap, BTreeSet}, path::PathBuf};
 use syntect::highlighting::{Theme, ThemeSet};
 use syntect::parsing::{
     Metadata, SyntaxDefinition, SyntaxReference, SyntaxSet, SyntaxSetBuilder,
@@ -41,6 +41,10 @@ struct Syntaxes {
 pub struct CodeHightlightConfig {
     themes: ThemePaths,
     syntaxes: Syntaxes,
+    // Content hashes of the theme files
+    theme_hashes: BTreeMap<Arc<Path>, String>,
+    // Content hash of all the syntax files, as syntaxes may include each other
+    syntaxes_hash: String,
 }
 
 impl CodeHightlightConfig {
@@ -49,7 +53,41 @@ impl CodeHightlightConfig {
         let themes = load_themes(themes_path);
         let syntaxes_path = config_path.join(SYNTAXES_DIR);
         let syntaxes = Syntaxes::load(syntaxes_path);
-        Self { themes, syntaxes }
+        let theme_hashes = themes
+            .values()
+            .filter_map(|(path, _)| Some((path.clone(), hash_files([path.as_ref()])?)))
+            .collect();
+        let syntaxes_hash = hash_files(
+            syntaxes
+                .syntax_paths_by_stem
+                .values()
+                .chain(syntaxes.metadata_paths_by_stem.values())
+                .map(Arc::as_ref),
+        )
+        .unwrap_or_default();
+        Self {
+            themes,
+            syntaxes,
+            theme_hashes,
+            syntaxes_hash,
+        }
+    }
+
+    // Hash of the light & dark theme files
+    pub fn theme_hash(&self, name: &str) -> Option<String> {
+        let (light, dark) = self.find_theme_path_pair(name)?;
+        let light = self.theme_hashes.get(light)?;
+        let dark = self.theme_hashes.get(dark)?;
+        Some(format!("{light}-{dark}"))
+    }
+
+    // Hash of the syntax files, empty for the syntaxes by default
+    pub fn syntax_hash(&self, token: &str) -> &str {
+        if self.is_syntax_by_default(token) {
+            ""
+        } else {
+            &self.syntaxes_hash
+        }
     }
 
     fn find_theme_pair(&self, name: &str, scheme: ColorScheme) -> Option<&(Arc<Path>, Theme)> {
@@ -74,6 +112,20 @@ impl CodeHightlightConfig {
         let light = &self.find_theme_pair(name, Light)?.1;
         Some((light, dark))
     }
+    // Names of the themes with both light & dark
+    pub fn theme_names(&self) -> BTreeSet<&str> {
+        self.themes
+            .keys()
+            .map(|name| {
+                [ColorScheme::Light, ColorScheme::Dark]
+                    .iter()
+                    .find_map(|scheme| name.strip_suffix(scheme.suffix()))
+                    .unwrap_or(name)
+            })
+            .filter(|name| self.find_theme(name).is_some())
+            .collect()
+    }
+
     pub fn find_theme_path(&self, name: &str) -> Option<Arc<Path>> {
         self.themes.get(name).map(|(path, _)| path).cloned()
     }
@@ -92,6 +144,10 @@ impl CodeHightlightConfig {
             .unwrap_or(default_syntax_set().find_syntax_plain_text())
     }
 
+    pub fn has_syntax(&self, token: &str) -> bool {
+        self.syntaxes.find(token).is_some()
+    }
+
     pub fn find_syntax_path(&self, token: &str) -> Option<Arc<Path>> {
         self.syntaxes.find_path(token)
     }
@@ -143,6 +199,18 @@ impl Display for CodeHightlightConfig {
     }
 }
 
+fn hash_files<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<String> {
+    let mut hasher = blake3::Hasher::new();
+    for path in paths {
+        let content = log_err_or_ok(
+            fs::read(path).with_context(|| format!("Failed to hash {}", path.display())),
+        )?;
+        hasher.update(path.as_os_str().as_encoded_bytes());
+        hasher.update(&content);
+    }
+    Some(hasher.finalize().to_hex().to_string())
+}
+
 fn load_themes(themes_path: PathBuf) -> ThemePaths {
     walk_glob!("{}/**/*.tmTheme", themes_path.display())
         .filter(|entry| entry.is_file())
@@ -7,11 +7,11 @@ use crate::config::schema::SchemaConfig;
 use crate::config::section::SectionConfig;
 use crate::util::html::Html;
 use crate::util::path::{dir_name, file_stem};
-use crate::util::path::{file_ext, format_path_ref};
+use crate::util::path::{file_ext, format_path_ref, relative_path};
 use anyhow::{Context, Result, anyhow};
 use embed::EmbedConfig;
 use sidebar::SidebarConfig;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub mod anchor;
@@ -32,13 +32,21 @@ const SYNTAXES_DIR: &str = "syntaxes";
 
 const SECTION_PATH: &str = "components/section.html";
 const HEADING_NUMBERING_PATH: &str = "components/heading-numbering.html";
+const FOOTER_DIR: &str = "components/footer/";
 const FOOTER_BACKLINKS_PATH: &str = "components/footer/backlinks.html";
 const FOOTER_REFERENCES_PATH: &str = "components/footer/references.html";
+const FOOTER_CITATION_PATH: &str = "components/footer/citation.html";
 const ANCHOR_DEF_PATH: &str = "components/anchor_def.html";
 const ANCHOR_GOTO_PATH: &str = "components/anchor_goto.html";
 const FOOTER_PATH: &str = "components/footer.html";
 const EMBED_PATH: &str = "components/embed.html";
 const EMBED_TITLE_PATH: &str = "components/embed_title.html";
+const EMBEDS_DIR: &str = "components/embeds/";
+
+const SEARCH_PATH: &str = "components/search.html";
+const SOCIAL_PATH: &str = "components/social.html";
+const LOCAL_GRAPH_PATH: &str = "components/localgraph.html";
+const SOCIAL_CARD_PATH: &str = "og.typ";
 
 const SIDEBAR_BLOCK_PATH: &str = "components/sidebar.html";
 const SIDEBAR_EACH_PATH: &str = "components/sidebar_each.html";
@@ -49,6 +57,10 @@ pub struct TypsiteConfig<'a> {
     pub anchor: AnchorConfig,
     pub sidebar: SidebarConfig,
     pub embed: EmbedConfig,
+    pub search: HtmlConfig,
+    pub social: HtmlConfig,
+    pub local_graph: HtmlConfig,
+    pub social_card: Option<Arc<Path>>, // Typst template of social preview images
     pub rules: RulesConfig,
     pub schemas: SchemaConfig,
     pub highlight: CodeHightlightConfig,
@@ -67,6 +79,12 @@ impl<'a> TypsiteConfig<'a> {
         let anchor = AnchorConfig::load(config_path)?;
         let sidebar = SidebarConfig::load(config_path)?;
         let embed = EmbedConfig::load(config_path)?;
+        let search = HtmlConfig::load_optional(config_path, SEARCH_PATH)?;
+        let social = HtmlConfig::load_optional(config_path, SOCIAL_PATH)?;
+        let local_graph = HtmlConfig::load_optional(config_path, LOCAL_GRAPH_PATH)?;
+        let social_card = Some(config_path.join(SOCIAL_CARD_PATH))
+            .filter(|path| path.exists())
+            .map(Arc::from);
         let rules = RulesConfig::load(config_path)?;
         let schemas = SchemaConfig::load(config_path)?;
         let highlight = CodeHightlightConfig::load(config_path);
@@ -84,6 +102,10 @@ impl<'a> TypsiteConfig<'a> {
             anchor,
             sidebar,
             embed,
+            search,
+            social,
+            local_graph,
+            social_card,
             schemas,
             highlight,
             config_path,
@@ -96,7 +118,8 @@ impl<'a> TypsiteConfig<'a> {
 
     pub fn path_ref(&'a self, path: &Path) -> Option<Arc<Path>> {
         if path.starts_with(self.typst_path) {
-            None
+            // Non-typst files, e.g. sources included by code blocks
+            (file_ext(path).as_deref() != Some("typ")).then(|| Arc::from(path))
         } else if path.starts_with(self.config_path) {
             let path = path.strip_prefix(self.config_path).ok()?;
             let path_str = path.as_os_str().to_str()?;
@@ -106,14 +129,24 @@ impl<'a> TypsiteConfig<'a> {
                 FOOTER_PATH => Some(self.footer.footer.path.clone()),
                 FOOTER_BACKLINKS_PATH => Some(self.footer.backlinks.path.clone()),
                 FOOTER_REFERENCES_PATH => Some(self.footer.references.path.clone()),
+                FOOTER_CITATION_PATH => Some(self.footer.citation.path.clone()),
                 ANCHOR_DEF_PATH => Some(self.anchor.define.path.clone()),
                 ANCHOR_GOTO_PATH => Some(self.anchor.goto.path.clone()),
                 SIDEBAR_BLOCK_PATH => Some(self.sidebar.block_path.clone()),
                 SIDEBAR_EACH_PATH => Some(self.sidebar.each_path.clone()),
-                EMBED_PATH => Some(self.embed.embed_path.clone()),
+                EMBED_PATH => Some(self.embed.embed.path.clone()),
                 EMBED_TITLE_PATH => Some(self.embed.embed_title_path.clone()),
+                SEARCH_PATH => Some(self.search.path.clone()),
+                SOCIAL_PATH => Some(self.social.path.clone()),
+                LOCAL_GRAPH_PATH => Some(self.local_graph.path.clone()),
+                path_str if path_str.starts_with(EMBEDS_DIR) => file_stem(path)
+                    .and_then(|name| self.embed.templates.get(name))
+                    .map(|template| template.path.clone()),
+                path_str if path_str.starts_with(FOOTER_DIR) => file_stem(path)
+                    .and_then(|name| self.footer.relations.get(name))
+                    .map(|relation| relation.path.clone()),
                 path_str if path_str.starts_with(RULES_DIR) => file_stem(path)
-                    .and_then(|rule| self.rules.get(rule))
+                    .and_then(|rule| self.rules.get(rule).ok())
                     .and_then(|rule| rule.path.clone()),
                 path_str if path_str.starts_with(SCHEMAS_DIR) => {
                     file_stem(path).and_then(|schema| {
@@ -135,13 +168,17 @@ impl<'a> TypsiteConfig<'a> {
                         })
                     })
                 }
-                _ => None,
+                _ => self.rules.find_file(&self.config_path.join(path)),
             }
         } else {
             None
         }
     }
 
+    pub fn config_path(&self) -> &Path {
+        self.config_path
+    }
+
     pub fn path_to_slug(&self, path: &Path) -> Result<String> {
         let path = if path.starts_with(self.typst_path) {
             path
@@ -166,6 +203,69 @@ impl<'a> TypsiteConfig<'a> {
             slug.to_string()
         }
     }
+
+    // The page path relative to the output dir,
+    // placed by its permalink if it has one, otherwise by its typst path
+    pub fn output_page_path(&self, path: &Path, permalink: Option<&str>) -> Result<PathBuf> {
+        match permalink {
+            Some(permalink) => Ok(PathBuf::from(format!(
+                "{}.html",
+                permalink.trim_start_matches('/')
+            ))),
+            None => relative_path(self.typst_path, path).map(|it| it.with_extension("html")),
+        }
+    }
+
+    // Social preview image, placed next to the page
+    pub fn output_card_path(&self, path: &Path, permalink: Option<&str>) -> Result<PathBuf> {
+        self.output_page_path(path, permalink)
+            .map(|page| page.with_extension("og.png"))
+    }
+
+    // Exported PDF, placed next to the page
+    pub fn output_pdf_path(&self, path: &Path, permalink: Option<&str>) -> Result<PathBuf> {
+        self.output_page_path(path, permalink)
+            .map(|page| page.with_extension("pdf"))
+    }
+
+    // Print view, placed next to the page
+    pub fn output_print_path(&self, path: &Path, permalink: Option<&str>) -> Result<PathBuf> {
+        self.output_page_path(path, permalink)
+            .map(|page| page.with_extension("print.html"))
+    }
+
+    // Local graph data, placed next to the page
+    pub fn output_local_graph_path(&self, path: &Path, permalink: Option<&str>) -> Result<PathBuf> {
+        self.output_page_path(path, permalink)
+            .map(|page| page.with_extension("graph.json"))
+    }
+}
+
+pub fn format_permalink(permalink: &str) -> String {
+    let permalink = permalink.trim().replace("\\", "/");
+    let permalink = permalink
+        .strip_suffix(".html")
+        .unwrap_or(permalink.as_str())
+        .trim_matches('/');
+    if permalink.is_empty() {
+        "/index".to_string()
+    } else {
+        format!("/{permalink}")
+    }
+}
+
+// Permalinks are output paths, so they must stay inside the output dir
+pub fn check_permalink(permalink: &str) -> Result<()> {
+    let invalid = permalink
+        .trim_start_matches('/')
+        .split('/')
+        .any(|segment| segment.is_empty() || segment == "." || segment == "..");
+    if invalid {
+        return Err(anyhow!(
+            "Invalid permalink {permalink}: empty, `.` and `..` segments are not allowed"
+        ));
+    }
+    Ok(())
 }
 
 pub struct HtmlConfig {
@@ -180,4 +280,19 @@ impl HtmlConfig {
         let Html { head, body } = Html::load(&path)?;
         Ok(Self { path, head, body })
     }
+
+    // Components added later are optional, so that older sites still load
+    pub fn load_optional(config: &Path, path: &str) -> Result<Self> {
+        let full_path = config.join(path);
+        if full_path.exists() {
+            Self::load(config, path)
+        } else {
+            let path = Arc::from(full_path);
+            Ok(Self {
+                path,
+                head: String::new(),
+                body: String::new(),
+            })
+        }
+    }
 }
@@ -6,8 +6,9 @@ use crate::pass::rewrite::*;
 use crate::util::error::log_err_or_ok;
 use crate::util::html::Attributes;
 use crate::util::html::HtmlWithTail;
-use crate::util::html::parse_first_tag;
+use crate::util::html::{parse_first_tag, take_tags};
 use crate::util::path::file_stem;
+use crate::util::str::did_you_mean;
 use crate::walk_glob;
 use anyhow::{Context, anyhow};
 use glob::glob;
@@ -27,7 +28,7 @@ impl RulesConfig {
         let mut rules = HashMap::new();
         let load_rules = walk_glob!("{}/**/*.html", rules_path.display())
             .par_bridge()
-            .map(TagRewriteRule::load_rule)
+            .map(|path| TagRewriteRule::load_rule(config_path, path))
             .collect::<Vec<anyhow::Result<(String, TagRewriteRule)>>>();
         rules.extend(load_rules.into_iter().filter_map(log_err_or_ok));
         let metacontent = TagRewriteRule::default(METACONTENT_TAG);
@@ -38,21 +39,47 @@ impl RulesConfig {
 
         anyhow::Ok(Self { rules })
     }
-    pub fn get(&self, tag: &str) -> Option<&TagRewriteRule> {
-        self.rules.get(tag)
+    pub fn get(&self, tag: &str) -> anyhow::Result<&TagRewriteRule> {
+        self.rules.get(tag).with_context(|| {
+            let suggestion = did_you_mean(tag, self.rules.keys().map(String::as_str));
+            format!("No rewrite rule named {tag}{suggestion}")
+        })
     }
 
     pub fn rule_name(&self, tag: &str) -> Option<&str> {
         self.rules.get_key_value(tag).map(|(k, _)| k.as_str())
     }
+
+    // A file declared by a rule
+    pub fn find_file(&self, path: &Path) -> Option<Arc<Path>> {
+        self.rules
+            .values()
+            .flat_map(|rule| rule.files.iter())
+            .find(|file| file.as_ref() == path)
+            .cloned()
+    }
 }
 
+// Declared by `<attribute name="..." default="..." />` in the head of a rule,
+// required if without default
+pub struct RuleAttribute {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+const ATTRIBUTE_KEY: &str = "attribute";
+
 pub struct TagRewriteRule {
     pub path: Option<Arc<Path>>,
     pub head: String,
     pub pass: Arc<dyn TagRewritePass>,
     pub body: String,
     pub tail: String,
+    pub attributes: Vec<RuleAttribute>,
+    // Other attributes of `<rewrite />`, passed to the pass over the ones of the tag
+    pub options: HashMap<String, String>,
+    // Files in the config dir declared by `files` or run by `command`
+    pub files: Vec<Arc<Path>>,
 }
 
 impl<'b, 'a: 'b> TagRewriteRule {
@@ -62,19 +89,26 @@ impl<'b, 'a: 'b> TagRewriteRule {
         let head = String::new();
         let body = String::new();
         let tail = String::new();
+        let attributes = Vec::new();
+        let options = HashMap::new();
+        let files = Vec::new();
         let rule = Self {
             path,
             pass,
             head,
             body,
             tail,
+            attributes,
+            options,
+            files,
         };
         Some(rule)
     }
 
-    fn load_rule(path: PathBuf) -> anyhow::Result<(String, Self)> {
+    fn load_rule(config_path: &Path, path: PathBuf) -> anyhow::Result<(String, Self)> {
         let mut tag: Option<String> = None;
         let mut pass: Option<String> = None;
+        let mut options = HashMap::new();
 
         let content = std::fs::read_to_string(&path)?;
         let mut tokenizer = Tokenizer::new(content.as_str());
@@ -84,6 +118,7 @@ impl<'b, 'a: 'b> TagRewriteRule {
                 let mut attr = Attributes::new(start.attributes);
                 tag = file_stem(&path).map(|s| s.to_string());
                 pass = Some(attr.take("pass")?);
+                options = attr.into_map();
                 anyhow::Ok(())
             }
             t => Err(anyhow!("Unexpected token {:?} in rewrite tag", t)),
@@ -99,6 +134,10 @@ impl<'b, 'a: 'b> TagRewriteRule {
             .map(|HtmlWithTail { head, body, tail }| (head, body, tail))?;
 
         let pass = find_rewrite_pass(&pass).with_context(|| format!("No rewrite pass called {pass}"))?;
+        let (head, attributes) = Self::take_attributes(&head)
+            .with_context(|| format!("Invalid attribute declaration in {}", path.display()))?;
+
+        let files = Self::declared_files(config_path, &options);
 
         let path = Some(Arc::from(path));
         let rule = Self {
@@ -107,15 +146,69 @@ impl<'b, 'a: 'b> TagRewriteRule {
             head,
             body,
             tail,
+            attributes,
+            options,
+            files,
         };
 
         anyhow::Ok((tag, rule))
     }
+
+    // `files` are separated by commas, the program of `command` counts if it's in the config dir
+    pub fn declared_files(config_path: &Path, options: &HashMap<String, String>) -> Vec<Arc<Path>> {
+        let files = options
+            .get("files")
+            .into_iter()
+            .flat_map(|files| files.split(','))
+            .map(str::trim)
+            .filter(|file| !file.is_empty());
+        let program = options
+            .get("command")
+            .and_then(|command| command.split_whitespace().next());
+        files
+            .chain(program)
+            .map(|file| config_path.join(file))
+            .filter(|file| file.is_file())
+            .map(Arc::from)
+            .collect()
+    }
+
+    fn take_attributes(head: &str) -> anyhow::Result<(String, Vec<RuleAttribute>)> {
+        let (head, tags) = take_tags(head, ATTRIBUTE_KEY)?;
+        let attributes = tags
+            .into_iter()
+            .map(|attrs| {
+                let name = attrs.expect("name")?.to_string();
+                let default = attrs.get("default").map(|it| it.to_string());
+                anyhow::Ok(RuleAttribute { name, default })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        anyhow::Ok((head, attributes))
+    }
+
+    // Fill the defaults of declared attributes, and reject the missing required ones
+    fn apply_attributes(&self, attrs: &mut Attributes) -> anyhow::Result<()> {
+        for attribute in &self.attributes {
+            if attrs.contains(&attribute.name) {
+                continue;
+            }
+            match &attribute.default {
+                Some(default) => attrs.insert(&attribute.name, default),
+                None => return Err(anyhow!("Rewrite: expect {} attribute", attribute.name)),
+            }
+        }
+        anyhow::Ok(())
+    }
+
     pub fn init(
         &self,
-        attrs: Attributes,
+        mut attrs: Attributes,
         passor: &mut PurePass,
     ) -> anyhow::Result<HashMap<String, String>> {
+        self.apply_attributes(&mut attrs)?;
+        for (key, value) in &self.options {
+            attrs.insert(key, value);
+        }
         self.pass.init(attrs, passor)
     }
 
@@ -2,6 +2,7 @@ use crate::config::SCHEMAS_DIR;
 use crate::util::error::log_err_or_ok;
 use crate::util::html::Html;
 use crate::util::path::file_stem;
+use crate::util::str::did_you_mean;
 use crate::walk_glob;
 use anyhow::*;
 use glob::glob;
@@ -34,7 +35,10 @@ impl SchemaConfig {
     }
 
     pub fn get(&self, id: &str) -> Result<&Schema> {
-        self.schemas.get(id).with_context(|| format!("No schema named {id}"))
+        self.schemas.get(id).with_context(|| {
+            let suggestion = did_you_mean(id, self.schemas.keys().map(String::as_str));
+            format!("No schema named {id}{suggestion}")
+        })
     }
 
 }
@@ -50,6 +54,8 @@ pub struct Schema {
     pub content: bool,
     pub sidebar: bool,
     pub footer: bool,
+    pub search: bool,
+    pub local_graph: bool,
     pub parent: bool, // If used parent's metacontent by {key@parent}
     pub head: String,
     pub body: String,
@@ -59,6 +65,8 @@ impl Schema {
         let mut content = false;
         let mut sidebar = false;
         let mut footer = false;
+        let mut search = false;
+        let mut local_graph = false;
         let Html { head, body } = Html::load_with_body_callback(&path, |token| {
             // let chains
             if let Token::StartTag(tag) = token {
@@ -72,6 +80,12 @@ impl Schema {
                     b"footer" => {
                         footer = true;
                     }
+                    b"search" => {
+                        search = true;
+                    }
+                    b"localgraph" => {
+                        local_graph = true;
+                    }
                     _ => {}
                 }
             }
@@ -85,6 +99,8 @@ impl Schema {
             content,
             sidebar,
             footer,
+            search,
+            local_graph,
             parent,
             head,
             body,
@@ -97,15 +113,26 @@ impl Schema {
         if self.content {
             files.insert(config.section.path.clone());
             files.insert(config.heading_numbering.path.clone());
+            files.insert(config.social.path.clone());
         }
         if self.sidebar {
             files.insert(config.sidebar.each_path.clone());
             files.insert(config.sidebar.block_path.clone());
         }
+        if self.search {
+            files.insert(config.search.path.clone());
+        }
+        if self.local_graph {
+            files.insert(config.local_graph.path.clone());
+        }
         if self.footer {
             files.insert(config.footer.footer.path.clone());
             files.insert(config.footer.backlinks.path.clone());
             files.insert(config.footer.references.path.clone());
+            files.insert(config.footer.citation.path.clone());
+            config.footer.relations.values().for_each(|relation| {
+                files.insert(relation.path.clone());
+            });
             let _ = config
                 .schemas
                 .get(BACKLINK_KEY)
@@ -12,6 +12,7 @@ use crate::ir::pending::{AnchorData, Pending};
 use crate::util::html::{OutputHead, OutputHtml};
 use anyhow::{Context, Result};
 use body::{Body, PureBody};
+use citation::Citation;
 use data::GlobalData;
 use dep::{Dependency, PureDependency, UpdatedIndex};
 use serde::{Deserialize, Serialize, Serializer};
@@ -20,9 +21,11 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock};
 
 pub mod body;
+pub mod citation;
 pub mod data;
 pub mod dep;
 pub mod sidebar;
+pub mod social;
 
 struct Cache<'a> {
     // body, sidebar
@@ -56,6 +59,7 @@ pub struct Article<'a> {
     full_sidebar: Sidebar,
     embed_sidebar: Sidebar,
     anchors: Vec<AnchorData>,
+    citations: Vec<Citation>,
     embeds: Vec<Embed>,
     dependency: Dependency,
     used_rules: HashSet<&'a str>,
@@ -98,6 +102,7 @@ impl<'c, 'b: 'c, 'a: 'b> Article<'a> {
             })
             .collect::<Vec<Option<_>>>();
         let anchors = pure.anchors;
+        let citations = pure.citations;
         if err.has_error() {
             return Err(err);
         }
@@ -120,6 +125,7 @@ impl<'c, 'b: 'c, 'a: 'b> Article<'a> {
             dependency,
             used_rules,
             anchors,
+            citations,
             cache: Cache::new(),
         };
         Ok(article)
@@ -138,6 +144,7 @@ impl<'c, 'b: 'c, 'a: 'b> Article<'a> {
         dependency: Dependency,
         used_rules: HashSet<&'a str>,
         anchors: Vec<AnchorData>,
+        citations: Vec<Citation>,
     ) -> Self {
         Article {
             slug,
@@ -152,6 +159,7 @@ impl<'c, 'b: 'c, 'a: 'b> Article<'a> {
             dependency,
             used_rules,
             anchors,
+            citations,
             cache: Cache::new(),
         }
     }
@@ -196,6 +204,14 @@ impl<'c, 'b: 'c, 'a: 'b> Article<'a> {
         &self.metadata.contents
     }
 
+    pub fn permalink(&self) -> Option<String> {
+        self.metadata.contents.permalink()
+    }
+
+    pub fn get_embeds(&self) -> &[Embed] {
+        &self.embeds
+    }
+
     pub fn get_meta_node(&self) -> &MetaNode {
         &self.metadata.node
     }
@@ -233,13 +249,53 @@ impl<'c, 'b: 'c, 'a: 'b> Article<'a> {
         self.dependency.unwrap(registry)
     }
 
-    pub fn get_depending_components(&self, config: &'a TypsiteConfig) -> HashSet<Arc<Path>> {
+    pub fn get_depending_components(
+        &self,
+        config: &'a TypsiteConfig,
+        articles: &HashMap<Key, Article<'a>>,
+    ) -> HashSet<Arc<Path>> {
         let mut components = self.schema.component_paths(config);
+        self.embed_components(config, articles, &mut HashSet::new(), &mut components);
+        components
+    }
+
+    // Articles of the custom relations in both directions
+    pub fn get_related_paths(&self, articles: &HashMap<Key, Article<'a>>) -> HashSet<Arc<Path>> {
+        let node = &self.metadata.node;
+        node.relations
+            .values()
+            .chain(node.inverse_relations.values())
+            .flatten()
+            .filter_map(|slug| articles.get(slug))
+            .map(|article| article.path.clone())
+            .collect()
+    }
+
+    // Only the templates chosen by the embeds,
+    // of the embedded articles as well, whose embeds are placed on this page
+    fn embed_components(
+        &self,
+        config: &'a TypsiteConfig,
+        articles: &HashMap<Key, Article<'a>>,
+        walked: &mut HashSet<Key>,
+        components: &mut HashSet<Arc<Path>>,
+    ) {
+        if !walked.insert(self.slug.clone()) {
+            return;
+        }
         if !self.metadata.node.children.is_empty() {
-            components.insert(config.embed.embed_path.clone());
             components.insert(config.embed.embed_title_path.clone());
         }
-        components
+        for embed in &self.embeds {
+            let template = config.embed.template(embed.template.as_deref());
+            components.insert(template.path.clone());
+        }
+        self.metadata
+            .node
+            .children
+            .iter()
+            .filter_map(|child| articles.get(child))
+            .for_each(|child| child.embed_components(config, articles, walked, components));
     }
 
     pub fn get_backlink(&self) -> Option<&OutputHtml<'a>> {
@@ -253,6 +309,10 @@ impl<'c, 'b: 'c, 'a: 'b> Article<'a> {
     pub fn get_anchors(&'b self) -> &'b Vec<AnchorData> {
         &self.anchors
     }
+
+    pub fn get_citations(&self) -> &[Citation] {
+        &self.citations
+    }
 }
 
 unsafe impl Send for Article<'_> {}
@@ -275,6 +335,8 @@ pub struct PureArticle {
     #[serde(serialize_with = "ordered_set")]
     used_rules: HashSet<String>,
     anchors: Vec<AnchorData>,
+    #[serde(default)]
+    citations: Vec<Citation>,
 }
 
 impl PureArticle {
@@ -290,6 +352,7 @@ impl PureArticle {
             body_content,
             article.body.rewriters,
             article.body.numberings,
+            article.body.sections,
         );
         let body = PureBody::from(body);
         let full_sidebar = article.full_sidebar.with_contents(full_sidebar);
@@ -308,6 +371,7 @@ impl PureArticle {
             .map(str::to_string)
             .collect();
         let anchors = article.anchors;
+        let citations = article.citations;
         PureArticle {
             slug,
             path,
@@ -321,8 +385,13 @@ impl PureArticle {
             dependency,
             used_rules,
             anchors,
+            citations,
         }
     }
+
+    pub fn permalink(&self) -> Option<String> {
+        self.metadata.permalink()
+    }
 }
 
 fn ordered_set<S>(value: &HashSet<String>, serializer: S) -> Result<S::Ok, S::Error>
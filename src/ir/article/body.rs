@@ -14,11 +14,19 @@ use crate::{
     },
 };
 
+// Body index ranges of a section, [start, end)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BodySection {
+    pub title: (usize, usize),
+    pub content: (usize, usize),
+}
+
 #[derive(Debug, Clone)]
 pub struct Body<'a> {
     pub content: Vec<String>,
     pub rewriters: Vec<BodyRewriter<'a>>,
     pub numberings: HashMap<Pos, usize>,
+    pub sections: HashMap<Pos, BodySection>,
 }
 
 impl<'a> Body<'a> {
@@ -26,11 +34,13 @@ impl<'a> Body<'a> {
         content: Vec<String>,
         rewriters: Vec<BodyRewriter<'a>>,
         numberings: HashMap<Pos, usize>,
+        sections: HashMap<Pos, BodySection>,
     ) -> Body<'a> {
         Body {
             content,
             rewriters,
             numberings,
+            sections,
         }
     }
     pub fn from(self_slug: Key, pure: PureBody, config: &'a TypsiteConfig) -> TypResult<Body<'a>> {
@@ -42,9 +52,10 @@ impl<'a> Body<'a> {
             .map(|rewriter| err.ok(BodyRewriter::from(self_slug.clone(), rewriter, config)))
             .collect::<Vec<Option<_>>>();
         let numberings = pure.numberings;
+        let sections = pure.sections;
         err.err_or(move || {
             let rewriters = rewriters.into_iter().flatten().collect();
-            Self::new(content, rewriters, numberings)
+            Self::new(content, rewriters, numberings, sections)
         })
     }
 }
@@ -54,6 +65,8 @@ pub struct PureBody {
     rewriters: Vec<PureRewriter>,
     #[serde(with = "pos_to_index_serde")]
     numberings: HashMap<Pos, usize>,
+    #[serde(default, with = "pos_to_index_serde")]
+    sections: HashMap<Pos, BodySection>,
 }
 
 impl From<Body<'_>> for PureBody {
@@ -61,20 +74,22 @@ impl From<Body<'_>> for PureBody {
         let content = body.content;
         let rewriters = body.rewriters.into_iter().map(PureRewriter::from).collect();
         let numberings = body.numberings;
+        let sections = body.sections;
         PureBody {
             content,
             rewriters,
             numberings,
+            sections,
         }
     }
 }
 
-mod pos_to_index_serde {
+pub(crate) mod pos_to_index_serde {
     use super::*;
     use serde::de::Error;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
-    pub fn serialize<S: Serializer>(
-        map: &HashMap<Pos, usize>,
+    pub fn serialize<S: Serializer, V: Serialize>(
+        map: &HashMap<Pos, V>,
         serializer: S,
     ) -> Result<S::Ok, S::Error> {
         let mut entries = Vec::with_capacity(map.len());
@@ -89,10 +104,10 @@ mod pos_to_index_serde {
         }
         entries.serialize(serializer)
     }
-    pub fn deserialize<'ce, D: Deserializer<'ce>>(
+    pub fn deserialize<'ce, D: Deserializer<'ce>, V: Deserialize<'ce>>(
         deserializer: D,
-    ) -> Result<HashMap<Pos, usize>, D::Error> {
-        let entries: Vec<(String, usize)> = Vec::deserialize(deserializer)?;
+    ) -> Result<HashMap<Pos, V>, D::Error> {
+        let entries: Vec<(String, V)> = Vec::deserialize(deserializer)?;
         let mut map = HashMap::with_capacity(entries.len());
         for (pos_str, index) in entries {
             let pos: Pos = if pos_str.is_empty() {
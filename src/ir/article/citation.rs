@@ -0,0 +1,71 @@
+use crate::util::html::strip_tags;
+use serde::{Deserialize, Serialize};
+
+// Elements taken as the paragraph around a citation, the innermost one wins
+const BLOCKS: [&str; 6] = ["p", "li", "dd", "td", "blockquote", "figcaption"];
+const CONTEXT_LIMIT: usize = 280;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    // Slug of the cited article
+    pub slug: String,
+    // Index of the cite rewriter in the body of the citing article
+    pub body_index: usize,
+    // Anchor of the enclosing heading in the citing article, empty before any heading
+    pub anchor: String,
+    // Plain text of the enclosing paragraph
+    pub context: String,
+}
+
+// Plain text of the innermost block around the citation,
+// `before` and `after` are the body contents around the body index of the citation.
+pub fn citation_context(before: &str, after: &str) -> String {
+    let start = BLOCKS
+        .iter()
+        .filter_map(|tag| Some((last_start_tag(before, tag)?, *tag)))
+        .max_by_key(|(start, _)| *start);
+    let Some((start, tag)) = start else {
+        return String::new();
+    };
+    let before = &before[start..];
+    // The block is closed before the citation
+    if before.contains(&format!("</{tag}>")) {
+        return String::new();
+    }
+    let end = after.find(&format!("</{tag}>")).unwrap_or(after.len());
+    let text = strip_tags(&format!("{before}{}", &after[..end]));
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(CONTEXT_LIMIT) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+fn last_start_tag(html: &str, tag: &str) -> Option<usize> {
+    let open = format!("<{tag}");
+    html.match_indices(&open)
+        .filter(|(index, _)| {
+            html[index + open.len()..]
+                .chars()
+                .next()
+                .is_some_and(|next| next == '>' || next.is_whitespace())
+        })
+        .map(|(index, _)| index)
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_of_citation() {
+        let before = "<h2>Title</h2><p>First.</p><p class=\"x\">As shown in <a href=\"/b\">";
+        let after = "B</a>, it <em>holds</em>.</p><pre>code</pre>";
+        assert_eq!(citation_context(before, after), "As shown in B, it holds.");
+        let before = "<ul><li><p>Done.</p></li><li>See <a href=\"/b\">";
+        assert_eq!(citation_context(before, "B</a></li></ul>"), "See B");
+        assert_eq!(citation_context("<p>Done.</p><a href=\"/b\">", "B</a>"), "");
+        assert_eq!(citation_context("<pre><a>", "</a></pre>"), "");
+    }
+}
@@ -15,6 +15,7 @@ use std::sync::OnceLock;
 
 use super::Article;
 use super::dep::Indexes;
+use super::social::social_head;
 
 pub struct GlobalData<'a, 'b, 'c> {
     pub config: &'a TypsiteConfig<'a>,
@@ -71,6 +72,11 @@ impl<'c, 'b: 'c, 'a: 'b> GlobalData<'a, 'b, 'c> {
         (body.content, full_sidebar, embed_sidebar)
     }
 
+    // Only the loaded articles have pendings
+    pub fn has_pending(&self, article: &Article<'a>) -> bool {
+        self.pendings.contains_key(article.slug.as_str())
+    }
+
     pub(super) fn get_pending_or_init(&'c self, article: &'b Article<'a>) -> &'c Pending<'c> {
         self.pendings
             .get(article.slug.as_str())
@@ -144,7 +150,6 @@ impl<'c, 'b: 'c, 'a: 'b> GlobalData<'a, 'b, 'c> {
 
     pub fn init_component_head(&'c self, article: &'b Article<'a>, head: &mut OutputHead<'a>) {
         let schema = article.schema;
-        let metadata = article.get_metadata();
         head.push(self.config.section.head.as_str());
         head.push(self.config.heading_numbering.head.as_str());
 
@@ -153,16 +158,48 @@ impl<'c, 'b: 'c, 'a: 'b> GlobalData<'a, 'b, 'c> {
             head.push(self.config.sidebar.block.head.as_str());
         }
 
-        if !metadata.node.children.is_empty() {
-            head.push(self.config.embed.embed.head.as_str());
-            head.push(self.config.embed.embed_title.head.as_str());
+        if schema.search {
+            head.push(self.config.search.head.as_str());
+        }
+
+        if schema.local_graph {
+            head.push(self.config.local_graph.head.as_str());
         }
 
+        self.init_embed_head(article, head, &mut HashSet::new());
+
         if !article.get_anchors().is_empty() {
             head.push(self.config.anchor.define.head.as_str());
             head.push(self.config.anchor.goto.head.as_str());
         }
     }
+    // Heads of the embed templates, of the embedded articles as well,
+    // whose embeds are placed on the page of the article
+    fn init_embed_head(
+        &'c self,
+        article: &'b Article<'a>,
+        head: &mut OutputHead<'a>,
+        walked: &mut HashSet<Key>,
+    ) {
+        if !walked.insert(article.slug.clone()) {
+            return;
+        }
+        let metadata = article.get_metadata();
+        if !metadata.node.children.is_empty() {
+            head.push(self.config.embed.embed_title.head.as_str());
+        }
+        for embed in article.get_embeds() {
+            let template = self.config.embed.template(embed.template.as_deref());
+            head.push(template.html.head.as_str());
+        }
+        metadata
+            .node
+            .children
+            .iter()
+            .filter_map(|it| self.article(it))
+            .for_each(|it| self.init_embed_head(it, head, walked));
+    }
+
     pub fn init_rewrite_head(&'c self, article: &'b Article<'a>, head: &mut OutputHead<'a>) {
         let metadata = article.get_metadata();
         let mut rules = article.all_used_rules(self).clone();
@@ -180,10 +217,11 @@ impl<'c, 'b: 'c, 'a: 'b> GlobalData<'a, 'b, 'c> {
             let mut heads = HashSet::new();
             for rule_id in rules.iter() {
                 let rule = self.config.rules.get(rule_id).unwrap();
-                heads.insert(&rule.head);
+                heads.insert(rule.head.as_str());
+                heads.extend(rule.pass.head());
             }
             for rule_head in heads {
-                head.push(rule_head.as_str());
+                head.push(rule_head);
             }
         }
     }
@@ -207,6 +245,9 @@ impl<'c, 'b: 'c, 'a: 'b> GlobalData<'a, 'b, 'c> {
             let mut head = OutputHead::empty();
             // Head
             head.start(metadata.inline(schema.head.as_str()));
+            if let Some(social) = social_head(self, article) {
+                head.start(social);
+            }
 
             self.init_component_head(article, &mut head);
             metadata
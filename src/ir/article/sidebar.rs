@@ -16,7 +16,10 @@ pub struct Sidebar {
     #[serde(with = "pos_to_sidebar_index_serde")]
     numberings: HashMap<Pos, SidebarIndexes>,
     #[serde(with = "pos_to_sidebar_index_serde")]
-    anchors: HashMap<Pos, SidebarIndexes>
+    anchors: HashMap<Pos, SidebarIndexes>,
+    // Content index ranges of the sections, [start, end)
+    #[serde(default, with = "crate::ir::article::body::pos_to_index_serde")]
+    sections: HashMap<Pos, (usize, usize)>,
 }
 impl Sidebar {
     pub fn new(
@@ -24,14 +27,16 @@ impl Sidebar {
         title_indexes: SidebarIndexes,
         show_children: SidebarIndexes,
         numbering: HashMap<Pos, SidebarIndexes>,
-        anchor: HashMap<Pos, SidebarIndexes>
+        anchor: HashMap<Pos, SidebarIndexes>,
+        sections: HashMap<Pos, (usize, usize)>,
     ) -> Self {
         Self {
             contents,
             title_indexes,
             show_children,
             numberings: numbering,
-            anchors: anchor
+            anchors: anchor,
+            sections,
         }
     }
 
@@ -61,6 +66,10 @@ impl Sidebar {
     pub fn indexes(&self) -> &SidebarIndexes {
         &self.show_children
     }
+
+    pub fn section(&self, pos: &Pos) -> Option<(usize, usize)> {
+        self.sections.get(pos).copied()
+    }
 }
 #[derive(Debug, PartialEq, Serialize, Deserialize, Copy, Clone)]
 pub enum SidebarType {
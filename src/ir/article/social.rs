@@ -0,0 +1,236 @@
+use crate::compile::options::Site;
+use crate::compile::registry::Key;
+use crate::compile::{compile_options, proj_options};
+use crate::ir::metadata::content::MetaContents;
+use crate::util::date::Date;
+use crate::util::html::{escape_xml, strip_tags};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+
+use super::Article;
+use super::data::GlobalData;
+
+const TITLE_KEY: &str = "title";
+const DESCRIPTION_KEY: &str = "description";
+const SUMMARY_KEY: &str = "summary";
+const AUTHOR_KEY: &str = "author";
+const DATE_KEY: &str = "date";
+const IMAGE_KEY: &str = "image";
+
+const OG_TITLE: &str = "{og-title}";
+const OG_DESCRIPTION: &str = "{og-description}";
+const OG_AUTHOR: &str = "{og-author}";
+const OG_DATE: &str = "{og-date}";
+const OG_IMAGE: &str = "{og-image}";
+const OG_URL: &str = "{og-url}";
+const OG_SITE_NAME: &str = "{og-site-name}";
+const TWITTER_CARD: &str = "{twitter-card}";
+const JSON_LD: &str = "{json-ld}";
+
+// Inline the social component head (Open Graph, Twitter card and JSON-LD) of an article
+pub fn social_head<'c, 'b: 'c, 'a: 'b>(
+    global_data: &'c GlobalData<'a, 'b, 'c>,
+    article: &'b Article<'a>,
+) -> Option<String> {
+    let component = global_data.config.social.head.as_str();
+    if component.trim().is_empty() {
+        return None;
+    }
+    let options = proj_options().ok()?;
+    let site = &options.site;
+    let pretty_url = compile_options()
+        .map(|options| options.pretty_url)
+        .unwrap_or(true);
+    let contents = article.get_meta_contents();
+    let text = |key: &str| {
+        contents
+            .get(key)
+            .map(|it| strip_tags(&it))
+            .unwrap_or_default()
+    };
+
+    let title = text(TITLE_KEY);
+    let description = Some(text(DESCRIPTION_KEY))
+        .filter(|it| !it.is_empty())
+        .unwrap_or_else(|| text(SUMMARY_KEY));
+    let author = text(AUTHOR_KEY);
+    let date = text(DATE_KEY);
+    let date = Date::parse(&date).map(|it| it.rfc3339()).unwrap_or(date);
+    // Fallback to the generated social card
+    let image = Some(text(IMAGE_KEY))
+        .filter(|it| !it.is_empty())
+        .or_else(|| {
+            global_data.config.social_card.as_ref()?;
+            let card = global_data
+                .config
+                .output_card_path(&article.path, article.permalink().as_deref())
+                .ok()?;
+            Some(card.to_string_lossy().replace('\\', "/"))
+        })
+        .map(|image| absolute_url(site, &image))
+        .unwrap_or_default();
+    let url = site.page_url(&contents.public_slug(), pretty_url);
+    let twitter_card = if image.is_empty() {
+        "summary"
+    } else {
+        "summary_large_image"
+    };
+
+    let mut article_ld = json!({
+        "@context": "https://schema.org",
+        "@type": "Article",
+        "headline": title,
+        "url": url,
+    });
+    let fields = [
+        ("description", &description),
+        ("datePublished", &date),
+        ("image", &image),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .for_each(|(key, value)| article_ld[key] = json!(value));
+    if !author.is_empty() {
+        article_ld["author"] = json!({ "@type": "Person", "name": author });
+    }
+    let breadcrumbs = breadcrumbs(global_data, article, site, pretty_url);
+    let json_ld = if breadcrumbs.len() > 1 {
+        json!([article_ld, breadcrumb_list(breadcrumbs)])
+    } else {
+        article_ld
+    };
+    // `</` must not close the script early
+    let json_ld = json_ld.to_string().replace("</", "<\\/");
+
+    let title = escape_xml(&title);
+    let description = escape_xml(&description);
+    let author = escape_xml(&author);
+    let date = escape_xml(&date);
+    let image = escape_xml(&image);
+    let url = escape_xml(&url);
+    let site_name = escape_xml(&site.title);
+    let head = contents.inline_with(
+        component,
+        &[
+            (OG_TITLE, &title),
+            (OG_DESCRIPTION, &description),
+            (OG_AUTHOR, &author),
+            (OG_DATE, &date),
+            (OG_IMAGE, &image),
+            (OG_URL, &url),
+            (OG_SITE_NAME, &site_name),
+            (TWITTER_CARD, twitter_card),
+            (JSON_LD, &json_ld),
+        ],
+    );
+    Some(drop_empty_tags(&head))
+}
+
+// Tags without a value are dropped, e.g. `og:image` of an article without image,
+// and so are the lines left blank by them
+fn drop_empty_tags(head: &str) -> String {
+    head.lines()
+        .filter_map(|line| {
+            let mut rest = line;
+            let mut kept = String::new();
+            while let Some(start) = rest.find('<') {
+                let end = rest[start..].find('>').map_or(rest.len(), |end| start + end + 1);
+                let tag = &rest[start..end];
+                let empty = (tag.starts_with("<meta") || tag.starts_with("<link"))
+                    && (tag.contains("content=\"\"") || tag.contains("href=\"\""));
+                kept.push_str(&rest[..start]);
+                if !empty {
+                    kept.push_str(tag);
+                }
+                rest = &rest[end..];
+            }
+            kept.push_str(rest);
+            (kept == line || !kept.trim().is_empty()).then_some(kept)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn absolute_url(site: &Site, url: &str) -> String {
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        site.url(url)
+    }
+}
+
+// (name, url) from the root ancestor to the article itself
+fn breadcrumbs<'c, 'b: 'c, 'a: 'b>(
+    global_data: &'c GlobalData<'a, 'b, 'c>,
+    article: &'b Article<'a>,
+    site: &Site,
+    pretty_url: bool,
+) -> Vec<(String, String)> {
+    let default_parent = proj_options().ok().and_then(|options| {
+        options
+            .default_metadata
+            .graph
+            .default_parent_slug(global_data.config, |slug| {
+                global_data.article(&slug).map(|it| it.slug.clone())
+            })
+    });
+    let crumb = |contents: &MetaContents| {
+        let name = contents
+            .get(TITLE_KEY)
+            .map(|it| strip_tags(&it))
+            .unwrap_or_default();
+        (name, site.page_url(&contents.public_slug(), pretty_url))
+    };
+    let mut visited: HashSet<Key> = HashSet::new();
+    let mut crumbs = Vec::new();
+    let mut current = Some(article);
+    while let Some(article) = current {
+        if !visited.insert(article.slug.clone()) {
+            break;
+        }
+        crumbs.push(crumb(article.get_meta_contents()));
+        let parent = article.get_metadata().node.parent.clone().or_else(|| {
+            default_parent
+                .clone()
+                .filter(|parent| parent.as_str() != article.slug.as_str())
+        });
+        current = parent.and_then(|parent| global_data.article(&parent));
+    }
+    crumbs.reverse();
+    crumbs
+}
+
+fn breadcrumb_list(crumbs: Vec<(String, String)>) -> Value {
+    let items = crumbs
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, url))| {
+            json!({
+                "@type": "ListItem",
+                "position": i + 1,
+                "name": name,
+                "item": url,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "@context": "https://schema.org",
+        "@type": "BreadcrumbList",
+        "itemListElement": items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drop_empty_meta_tags() {
+        let head = "<meta property=\"og:title\" content=\"Title\" /><meta property=\"og:image\" content=\"\" />\n    <link rel=\"canonical\" href=\"\" />\n<script>{}</script>";
+        assert_eq!(
+            drop_empty_tags(head),
+            "<meta property=\"og:title\" content=\"Title\" />\n<script>{}</script>"
+        );
+    }
+}
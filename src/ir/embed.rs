@@ -1,9 +1,11 @@
 
 use crate::compile::registry::{Key, KeyRegistry};
-use crate::ir::article::sidebar::{SidebarIndexes, SidebarPos};
+use crate::ir::article::sidebar::{Pos, SidebarIndexes, SidebarPos};
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 
+pub mod variable;
+
 pub type EmbedVariables = Vec<(String,String)>;
 
 pub struct Embed {
@@ -11,6 +13,10 @@ pub struct Embed {
     pub open: bool,
     pub variables: EmbedVariables,
     pub section_type: SectionType,
+    // Only the section at the position is embedded
+    pub section: Option<Pos>,
+    // Name of the template in `components/embeds/`, the default one if none
+    pub template: Option<String>,
     pub sidebar_pos: SidebarPos,
     pub full_sidebar_indexes: SidebarIndexes,  
     pub embed_sidebar_indexes: SidebarIndexes, 
@@ -23,6 +29,8 @@ impl Embed {
         open: bool,
         variables: EmbedVariables,
         section_type: SectionType,
+        section: Option<Pos>,
+        template: Option<String>,
         sidebar_pos: SidebarPos,
         full_sidebar_indexes: SidebarIndexes,
         embed_sidebar_indexes: SidebarIndexes,
@@ -33,6 +41,8 @@ impl Embed {
             open,
             variables,
             section_type,
+            section,
+            template,
             sidebar_pos,
             full_sidebar_indexes,
             embed_sidebar_indexes,
@@ -48,6 +58,8 @@ impl Embed {
                 pure.open,
                 pure.variables,
                 pure.section_type,
+                pure.section,
+                pure.template,
                 pure.sidebar_pos,
                 pure.full_sidebar_indexes,
                 pure.embed_sidebar_indexes,
@@ -66,6 +78,10 @@ pub struct PureEmbed {
     open: bool,
     variables: EmbedVariables,
     section_type: SectionType,
+    #[serde(default)]
+    section: Option<Pos>,
+    #[serde(default)]
+    template: Option<String>,
     sidebar_pos: SidebarPos,
     full_sidebar_indexes: SidebarIndexes,
     embed_sidebar_indexes: SidebarIndexes,
@@ -79,6 +95,8 @@ impl From<Embed> for PureEmbed {
             open: embed.open,
             variables: embed.variables,
             section_type: embed.section_type,
+            section: embed.section,
+            template: embed.template,
             sidebar_pos: embed.sidebar_pos,
             full_sidebar_indexes: embed.full_sidebar_indexes,
             embed_sidebar_indexes: embed.embed_sidebar_indexes,
@@ -108,6 +126,18 @@ impl<T: AsRef<str>> From<T> for SectionType {
     }
 }
 
+// Position of a section by its heading anchor ("notes/a-2.1" or "#notes/a-2.1"),
+// or by its heading numbering ("2.1")
+pub fn parse_section(section: &str) -> Option<Pos> {
+    let section = section.trim().trim_start_matches('#');
+    let numbering = section.rsplit('-').next()?;
+    numbering
+        .split('.')
+        .map(|it| it.parse::<usize>().ok()?.checked_sub(1))
+        .collect::<Option<Pos>>()
+        .filter(|pos| !pos.is_empty())
+}
+
 // PureAtom::Embed { slug, open, sidebar_pos, index } => match registry.know(slug,"Embed",self_slug) {
 // Some(slug) => Atom::Embed { slug, open, sidebar_pos, index },
 // None =>Atom::Plain
@@ -121,3 +151,17 @@ impl<T: AsRef<str>> From<T> for SectionType {
 // sidebar_pos: Option<(Vec<usize>,usize)>,
 // index: usize
 // },
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section_by_anchor_or_numbering() {
+        assert_eq!(parse_section("2.1"), Some(vec![1, 0]));
+        assert_eq!(parse_section("#notes/a-b-3"), Some(vec![2]));
+        assert_eq!(parse_section("notes/a-1.2.1"), Some(vec![0, 1, 0]));
+        assert_eq!(parse_section("0.1"), None);
+        assert_eq!(parse_section("intro"), None);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::ir::embed::EmbedVariables;
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Copy, Clone)]
+pub enum VariableType {
+    String,
+    Bool,
+    Number,
+}
+
+impl VariableType {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "string" | "str" => Ok(VariableType::String),
+            "bool" => Ok(VariableType::Bool),
+            "number" | "int" | "float" => Ok(VariableType::Number),
+            _ => Err(anyhow!(
+                "Invalid variable type: {s}, expect 'string' | 'bool' | 'number'"
+            )),
+        }
+    }
+
+    fn check(&self, value: &str) -> bool {
+        match self {
+            VariableType::String => true,
+            VariableType::Bool => value == "true" || value == "false",
+            VariableType::Number => value.trim().parse::<f64>().is_ok(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            VariableType::String => "string",
+            VariableType::Bool => "bool",
+            VariableType::Number => "number",
+        }
+    }
+}
+
+// A variable accepted by embeds, declared by the embedded article or the embed component
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct EmbedVariable {
+    pub name: String,
+    pub kind: VariableType,
+    // Variables without default are required
+    pub default: Option<String>,
+}
+
+impl EmbedVariable {
+    pub fn new(name: String, kind: &str, default: Option<String>) -> Result<Self> {
+        let kind = VariableType::parse(kind)?;
+        if let Some(default) = &default
+            && !kind.check(default)
+        {
+            bail!(
+                "Default value `{default}` of variable `{name}` is not a {}",
+                kind.name()
+            );
+        }
+        Ok(Self {
+            name,
+            kind,
+            default,
+        })
+    }
+
+    fn key(&self) -> String {
+        format!("{{{}}}", self.name)
+    }
+}
+
+// Declarations of the article take precedence over the ones of the component
+pub fn merge_declarations<'a>(
+    article: &'a [EmbedVariable],
+    component: &'a [EmbedVariable],
+) -> Vec<&'a EmbedVariable> {
+    let mut declared: Vec<&EmbedVariable> = article.iter().collect();
+    component
+        .iter()
+        .filter(|it| article.iter().all(|decl| decl.name != it.name))
+        .for_each(|it| declared.push(it));
+    declared
+}
+
+// Check the variables of an embed against the declarations,
+// every variable must be declared, typed as declared, and required ones must be given.
+pub fn check_variables(variables: &EmbedVariables, declared: &[&EmbedVariable]) -> Result<()> {
+    let mut errors = Vec::new();
+    for (key, value) in variables {
+        match declared.iter().find(|decl| &decl.key() == key) {
+            Some(decl) if !decl.kind.check(value) => errors.push(format!(
+                "variable {key} expects a {}, but got `{value}`",
+                decl.kind.name()
+            )),
+            Some(_) => {}
+            None => errors.push(format!("undeclared variable {key}")),
+        }
+    }
+    for decl in declared {
+        let key = decl.key();
+        if decl.default.is_none() && variables.iter().all(|(it, _)| it != &key) {
+            errors.push(format!("missing required variable {key}"));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(errors.join(", ")))
+    }
+}
+
+// Variables of the embed, with defaults of the missing declared ones
+pub fn with_defaults(variables: &EmbedVariables, declared: &[EmbedVariable]) -> EmbedVariables {
+    let mut variables = variables.clone();
+    for decl in declared {
+        let key = decl.key();
+        if let Some(default) = &decl.default
+            && variables.iter().all(|(it, _)| it != &key)
+        {
+            variables.push((key, default.clone()));
+        }
+    }
+    variables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_and_default_variables() {
+        let article = vec![EmbedVariable::new("tag".into(), "string", None).unwrap()];
+        let component = vec![
+            EmbedVariable::new("show-metadata".into(), "bool", Some("true".into())).unwrap(),
+            EmbedVariable::new("tag".into(), "string", Some("x".into())).unwrap(),
+        ];
+        assert!(EmbedVariable::new("width".into(), "number", Some("wide".into())).is_err());
+        let declared = merge_declarations(&article, &component);
+        assert_eq!(declared.len(), 2);
+
+        let variables = vec![("{tag}".to_string(), "note".to_string())];
+        assert!(check_variables(&variables, &declared).is_ok());
+        // `tag` is required by the article
+        assert!(check_variables(&Vec::new(), &declared).is_err());
+        let typo = vec![
+            ("{tag}".to_string(), "note".to_string()),
+            ("{show-metdata}".to_string(), "false".to_string()),
+        ];
+        assert!(check_variables(&typo, &declared).is_err());
+        let mistyped = vec![
+            ("{tag}".to_string(), "note".to_string()),
+            ("{show-metadata}".to_string(), "yes".to_string()),
+        ];
+        assert!(check_variables(&mistyped, &declared).is_err());
+
+        let declared: Vec<EmbedVariable> = declared.into_iter().cloned().collect();
+        assert_eq!(
+            with_defaults(&variables, &declared),
+            vec![
+                ("{tag}".to_string(), "note".to_string()),
+                ("{show-metadata}".to_string(), "true".to_string()),
+            ]
+        );
+    }
+}
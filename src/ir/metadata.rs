@@ -4,8 +4,8 @@ pub mod options;
 
 use crate::compile::error::TypResult;
 use crate::compile::registry::{Key, KeyRegistry};
-use crate::config::TypsiteConfig;
-use crate::ir::metadata::content::{MetaContents, PureMetaContents};
+use crate::config::{TypsiteConfig, format_permalink};
+use crate::ir::metadata::content::{MetaContents, PERMALINK_KEY, PureMetaContents};
 use crate::ir::metadata::graph::{MetaNode, PureMetaNode};
 use crate::ir::metadata::options::MetaOptions;
 use serde::{Deserialize, Serialize};
@@ -55,6 +55,17 @@ pub struct PureMetadata {
 
     node: PureMetaNode,
 }
+impl PureMetadata {
+    pub fn permalink(&self) -> Option<String> {
+        self.contents
+            .contents
+            .get(PERMALINK_KEY)
+            .map(|content| content.body.join(""))
+            .filter(|it| !it.trim().is_empty())
+            .map(|it| format_permalink(&it))
+    }
+}
+
 impl From<Metadata<'_>> for PureMetadata {
     fn from(metadata: Metadata) -> Self {
         let contents = PureMetaContents::from(metadata.contents);
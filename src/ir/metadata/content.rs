@@ -1,7 +1,7 @@
 use crate::compile::error::{TypError, TypResult};
 use crate::compile::registry::Key;
 use crate::compile::{compile_options, proj_options};
-use crate::config::TypsiteConfig;
+use crate::config::{TypsiteConfig, format_permalink};
 use crate::ir::article::data::GlobalData;
 use crate::ir::article::dep::Indexes;
 use crate::ir::rewriter::{MetaRewriter, PureRewriter};
@@ -13,6 +13,8 @@ use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, OnceLock};
 
 pub const TITLE_KEY: &str = "title";
+pub const PERMALINK_KEY: &str = "permalink";
+pub const ALIASES_KEY: &str = "aliases";
 pub const TITLE_REPLACEMENT: &str = "{title}";
 pub const PAGE_TITLE_REPLACEMENT: &str = "{page-title}";
 pub const PAGE_TITLE_REPLACEMENT_: &str = "{page_title}";
@@ -87,6 +89,27 @@ impl<'b, 'a: 'b> MetaContents<'a> {
         })
     }
 
+    // Permalink overrides the path-based slug in URLs
+    pub fn permalink(&self) -> Option<String> {
+        self.contents
+            .get(PERMALINK_KEY)
+            .map(|content| content.get())
+            .filter(|it| !it.trim().is_empty())
+            .map(|it| format_permalink(&it))
+    }
+
+    pub fn aliases(&self) -> Vec<String> {
+        self.contents
+            .get(ALIASES_KEY)
+            .map(|content| split_aliases(&content.get()))
+            .unwrap_or_default()
+    }
+
+    // The slug shown in URLs
+    pub fn public_slug(&self) -> String {
+        self.permalink().unwrap_or_else(|| self.slug.to_string())
+    }
+
     pub(crate) fn keys(&self) -> HashSet<&str> {
         self.contents.keys().map(|k| k.as_str()).collect()
     }
@@ -99,14 +122,15 @@ impl<'b, 'a: 'b> MetaContents<'a> {
                 .map(|(k, v)| (format!("{{{k}}}"), v.get().to_string()))
                 .collect::<HashMap<_, _>>();
             let compile_options = compile_options().unwrap();
+            let public_slug = self.public_slug();
             // Short slug
             let slug_display = if compile_options.short_slug {
-                self.slug
+                public_slug
                     .split('/')
                     .next_back()
-                    .unwrap_or(self.slug.as_str())
+                    .unwrap_or(public_slug.as_str())
             } else {
-                self.slug.as_str()
+                public_slug.as_str()
             };
 
             map.insert(
@@ -119,9 +143,9 @@ impl<'b, 'a: 'b> MetaContents<'a> {
             );
 
             let slug = if !compile_options.pretty_url {
-                format!("{}.html", self.slug)
+                format!("{public_slug}.html")
             } else {
-                self.slug.to_string()
+                public_slug.to_string()
             };
 
             map.insert(SLUG_REPLACEMENT.to_string(), slug.to_string());
@@ -258,6 +282,14 @@ impl<'b, 'a: 'b> MetaContents<'a> {
     }
 }
 
+pub fn split_aliases(aliases: &str) -> Vec<String> {
+    aliases
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|it| !it.is_empty())
+        .map(format_permalink)
+        .collect()
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct MetaContent<'a> {
     content: Vec<String>,
@@ -307,7 +339,7 @@ impl<'b, 'a: 'b> MetaContent<'a> {
         })
     }
 
-    fn get(&self) -> Arc<str> {
+    pub(crate) fn get(&self) -> Arc<str> {
         self.content_str
             .get_or_init(|| {
                 let str = if let Some(body) = self.content_cache.get() {
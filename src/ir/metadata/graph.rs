@@ -3,7 +3,7 @@ use crate::compile::{
     registry::{Key, KeyRegistry},
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct MetaNode {
@@ -19,6 +19,10 @@ pub struct MetaNode {
     pub references: HashSet<Key>,
     // Articles that are embedded in this article
     pub children: HashSet<Key>,
+    // Custom relations declared in this article, relation name -> articles
+    pub relations: HashMap<String, HashSet<Key>>,
+    // Custom relations declared by other articles, inverse name -> articles
+    pub inverse_relations: HashMap<String, HashSet<Key>>,
 }
 
 impl MetaNode {
@@ -47,6 +51,20 @@ impl MetaNode {
             .into_iter()
             .map(|p| err.ok(registry.know(p, "Children", slug.as_str())))
             .collect::<Vec<Option<_>>>();
+        let mut know_relations = |relations: HashMap<String, HashSet<String>>| {
+            relations
+                .into_iter()
+                .map(|(name, slugs)| {
+                    let slugs = slugs
+                        .into_iter()
+                        .filter_map(|p| err.ok(registry.know(p, "Relations", slug.as_str())))
+                        .collect();
+                    (name, slugs)
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        let relations = know_relations(pure.relations);
+        let inverse_relations = know_relations(pure.inverse_relations);
         err.err_or(|| MetaNode {
             slug,
             parent,
@@ -54,8 +72,20 @@ impl MetaNode {
             backlinks: backlinks.into_iter().flatten().collect(),
             references: references.into_iter().flatten().collect(),
             children: children.into_iter().flatten().collect(),
+            relations,
+            inverse_relations,
         })
     }
+
+    // Articles related by the name, declared either here or by the other article
+    pub fn related(&self, name: &str) -> HashSet<&Key> {
+        self.relations
+            .get(name)
+            .into_iter()
+            .chain(self.inverse_relations.get(name))
+            .flatten()
+            .collect()
+    }
     pub fn refs_and_backlinks(&self) -> HashSet<&Key> {
         self.references
             .iter()
@@ -71,6 +101,10 @@ pub struct PureMetaNode {
     pub backlinks: HashSet<String>,
     pub cited: HashSet<String>,
     pub children: HashSet<String>,
+    #[serde(default)]
+    pub relations: HashMap<String, HashSet<String>>,
+    #[serde(default)]
+    pub inverse_relations: HashMap<String, HashSet<String>>,
 }
 
 impl From<MetaNode> for PureMetaNode {
@@ -81,10 +115,19 @@ impl From<MetaNode> for PureMetaNode {
             backlinks: node.backlinks.into_iter().map(|s| s.to_string()).collect(),
             cited: node.references.into_iter().map(|s| s.to_string()).collect(),
             children: node.children.into_iter().map(|s| s.to_string()).collect(),
+            relations: pure_relations(node.relations),
+            inverse_relations: pure_relations(node.inverse_relations),
         }
     }
 }
 
+fn pure_relations(relations: HashMap<String, HashSet<Key>>) -> HashMap<String, HashSet<String>> {
+    relations
+        .into_iter()
+        .map(|(name, slugs)| (name, slugs.into_iter().map(|s| s.to_string()).collect()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +143,11 @@ mod tests {
             children: vec!["test3".to_string(), "test4".to_string()]
                 .into_iter()
                 .collect(),
+            relations: HashMap::from([(
+                "prerequisite".to_string(),
+                vec!["test5".to_string()].into_iter().collect(),
+            )]),
+            inverse_relations: HashMap::new(),
         };
         let json = serde_json::to_string(&metadata).unwrap();
         let metadata_de = serde_json::from_str(&json).unwrap();
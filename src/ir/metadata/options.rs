@@ -1,8 +1,14 @@
 use crate::ir::article::sidebar::{HeadingNumberingStyle, SidebarType};
+use crate::ir::embed::variable::EmbedVariable;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MetaOptions {
     pub heading_numbering_style: HeadingNumberingStyle,
     pub sidebar_type: SidebarType,
+    #[serde(default)]
+    pub pdf: bool,
+    // Variables accepted when the article is embedded
+    #[serde(default)]
+    pub embed_variables: Vec<EmbedVariable>,
 }
@@ -2,9 +2,11 @@ use crate::compile::registry::Key;
 use crate::config::TypsiteConfig;
 use crate::config::anchor::AnchorConfig;
 use crate::config::heading_numbering::HeadingNumberingConfig;
+use crate::ir::article::body::BodySection;
 use crate::ir::article::data::GlobalData;
 use crate::ir::article::sidebar::{HeadingNumberingStyle, Pos, SidebarIndexes, SidebarType};
 use crate::ir::embed::SectionType;
+use crate::ir::embed::variable::{EmbedVariable, with_defaults};
 use crate::util::str::{SectionElem, ac_replace};
 use crate::util::{pos_base_on, pos_slug};
 use serde::{Deserialize, Serialize};
@@ -32,13 +34,14 @@ impl BodyNumberingData {
         base_anchor: Option<&Pos>,
         base_numbering: Option<&Pos>,
         style: HeadingNumberingStyle,
+        section: Option<&Pos>,
         body: &mut [String],
     ) {
         let numbering = config.get_with_pos_anchor(
             style,
             base_anchor,
             base_numbering,
-            &self.pos,
+            &relative(&self.pos, section),
             self.anchor.as_str(),
         );
         body[self.body_index] = numbering.clone();
@@ -59,14 +62,15 @@ impl SidebarData {
         base_numbering: Option<&Pos>,
         section_type: SectionType,
         style: HeadingNumberingStyle,
+        section: Option<&Pos>,
         sidebar: &mut [String],
     ) {
         self.indexes.based_on(section_type, sidebar);
         for numbering in self.numberings.iter() {
-            numbering.based_on(config, base_anchor, base_numbering, style, sidebar);
+            numbering.based_on(config, base_anchor, base_numbering, style, section, sidebar);
         }
         for anchor in self.anchors.iter() {
-            anchor.based_on(base_anchor, sidebar);
+            anchor.based_on(base_anchor, section, sidebar);
         }
     }
 
@@ -102,9 +106,11 @@ impl SidebarNumberingData {
         base_anchor: Option<&Pos>,
         base_numbering: Option<&Pos>,
         style: HeadingNumberingStyle,
+        section: Option<&Pos>,
         sidebar: &mut [String],
     ) {
-        let numbering = config.get_with_pos_anchor(style, base_anchor,base_numbering, &self.pos, self.anchor.as_str());
+        let pos = relative(&self.pos, section);
+        let numbering = config.get_with_pos_anchor(style, base_anchor,base_numbering, &pos, self.anchor.as_str());
         for &index in &self.sidebar_indexes {
             sidebar[index] = numbering.clone();
         }
@@ -126,9 +132,10 @@ impl SidebarAnchorData {
     fn based_on(
         &self,
         base_anchor: Option<&Pos>,
+        section: Option<&Pos>,
         sidebar: &mut [String],
     ) {
-        let pos_anchor = pos_base_on(base_anchor, Some(&self.pos));
+        let pos_anchor = pos_base_on(base_anchor, Some(&relative(&self.pos, section)));
         let anchor = pos_slug(&pos_anchor, &self.anchor);
         for &index in &self.sidebar_indexes {
             sidebar[index] = anchor.clone();
@@ -161,11 +168,49 @@ pub struct EmbedData<'c> {
     embed_sidebar_indexes: SidebarIndexes,
     open: bool,
     variables: EmbedVariables,
+    template: Option<String>,
+    // Declared variables, of which the defaults fill the missing ones
+    declared: Vec<EmbedVariable>,
     title: String,
     full_sidebar_title_indexes: SidebarIndexes,
     embed_sidebar_title_indexes: SidebarIndexes,
     pending: &'c Pending<'c>,
     pub section_type: SectionType,
+    section: Option<EmbedSection>,
+}
+
+// A single section of the embedded article
+pub struct EmbedSection {
+    pos: Pos,
+    body: BodySection,
+    // Range of the section in the full sidebar
+    full_sidebar: Option<(usize, usize)>,
+    // Entries of the embeds out of the section in the embed sidebar
+    embed_sidebar_excluded: SidebarIndexes,
+}
+
+impl EmbedSection {
+    pub fn new(
+        pos: Pos,
+        body: BodySection,
+        full_sidebar: Option<(usize, usize)>,
+        embed_sidebar_excluded: SidebarIndexes,
+    ) -> Self {
+        Self {
+            pos,
+            body,
+            full_sidebar,
+            embed_sidebar_excluded,
+        }
+    }
+}
+
+// Position relative to the embedded section, of which the section itself is the root
+fn relative(pos: &Pos, section: Option<&Pos>) -> Pos {
+    match section {
+        Some(section) if pos.starts_with(section) => pos[section.len()..].to_vec(),
+        _ => pos.clone(),
+    }
 }
 
 impl<'c> EmbedData<'c> {
@@ -178,10 +223,13 @@ impl<'c> EmbedData<'c> {
         embed_sidebar_indexes: SidebarIndexes,
         open: bool,
         variables: EmbedVariables,
+        template: Option<String>,
+        declared: Vec<EmbedVariable>,
         title: String,
         full_sidebar_title_indexes: SidebarIndexes,
         embed_sidebar_title_indexes: SidebarIndexes,
         child: &'c Pending,
+        section: Option<EmbedSection>,
     ) -> Self {
         Self {
             pos,
@@ -192,10 +240,13 @@ impl<'c> EmbedData<'c> {
             embed_sidebar_indexes,
             open,
             variables,
+            template,
+            declared,
             title,
             full_sidebar_title_indexes,
             embed_sidebar_title_indexes,
             pending: child,
+            section,
         }
     }
 
@@ -206,11 +257,12 @@ impl<'c> EmbedData<'c> {
         base_anchor: Option<&Pos>,
         base_numbering: Option<&Pos>,
         parent_style: HeadingNumberingStyle,
+        parent_section: Option<&Pos>,
         body: &mut [String],
         sidebar: &mut [String],
         sidebar_type: SidebarType,
     ) {
-        let pos = &self.pos;
+        let pos = &relative(&self.pos, parent_section);
         let metadata = global_data.metadata(self.slug.as_str()).unwrap();
         let numbering = config.heading_numbering.get_with_pos_anchor(
             parent_style,
@@ -223,7 +275,7 @@ impl<'c> EmbedData<'c> {
         let mut embed_article_body = Vec::new();
         let mut embed_article_sidebar = String::new();
 
-        let embed_config = &config.embed.embed;
+        let embed_config = &config.embed.template(self.template.as_deref()).html;
 
         let pos_anchor = Some(pos);
         let pos_numebring = match parent_style {
@@ -234,44 +286,77 @@ impl<'c> EmbedData<'c> {
         let base_anchor = Some(pos_base_on(base_anchor, pos_anchor));
         let base_numbering = Some(pos_base_on(base_numbering, pos_numebring));
 
-        let (body_vec, mut full_sidebar_vec, mut embed_sidebar_vec) = self.pending.based_on(
+        let section = self.section.as_ref();
+        let (body_vec, mut full_sidebar_vec, mut embed_sidebar_vec) = self.pending.based_on_section(
             config,
             global_data,
             base_anchor.as_ref(),
             base_numbering.as_ref(),
             sidebar_type,
             self.section_type,
+            section.map(|section| &section.pos),
         );
 
-        if sidebar_type == SidebarType::All {
-            match self.section_type {
-                SectionType::None => {}
-                _ => {
-                    for &title_index in &self.full_sidebar_title_indexes {
-                        full_sidebar_vec[title_index] = self.title.clone();
-                    }
-                    embed_article_sidebar = full_sidebar_vec.join("");
+        // The title of the section in the embed title, and only its content
+        let (title, content) = match section {
+            Some(section) => {
+                let (start, end) = section.body.title;
+                let title = ac_replace(
+                    &config.embed.embed_title.body,
+                    &[("{title}", body_vec[start..end].join("").trim())],
+                );
+                let (start, end) = section.body.content;
+                (metadata.inline(&title), body_vec[start..end].join(""))
+            }
+            None => (self.title.clone(), body_vec.join("")),
+        };
+
+        // Children of the entries are hidden by `show-children` for `SectionType::OnlyTitle`
+        match (self.section_type, section, sidebar_type) {
+            (SectionType::None, _, _) => {}
+            (_, Some(section), SidebarType::All) => {
+                // The entry of the section, titled by its heading
+                if let Some((start, end)) = section.full_sidebar {
+                    embed_article_sidebar = full_sidebar_vec[start..end].join("");
                 }
             }
-        } else {
-            for &title_index in &self.embed_sidebar_title_indexes {
-                embed_sidebar_vec[title_index] = self.title.clone();
+            (_, Some(section), SidebarType::OnlyEmbed) => {
+                // The embed sidebar has no headings, so it's titled by the section,
+                // and only the embeds in the section are kept
+                for &index in &section.embed_sidebar_excluded {
+                    embed_sidebar_vec[index] = String::new();
+                }
+                for &title_index in &self.embed_sidebar_title_indexes {
+                    embed_sidebar_vec[title_index] = title.clone();
+                }
+                embed_article_sidebar = embed_sidebar_vec.join("");
             }
-            embed_article_sidebar = embed_sidebar_vec.join("")
-        };
+            (_, None, SidebarType::All) => {
+                for &title_index in &self.full_sidebar_title_indexes {
+                    full_sidebar_vec[title_index] = self.title.clone();
+                }
+                embed_article_sidebar = full_sidebar_vec.join("");
+            }
+            (_, None, SidebarType::OnlyEmbed) => {
+                for &title_index in &self.embed_sidebar_title_indexes {
+                    embed_sidebar_vec[title_index] = self.title.clone();
+                }
+                embed_article_sidebar = embed_sidebar_vec.join("");
+            }
+        }
 
         for elem in &embed_config.body {
             let str = match elem {
                 SectionElem::Plain(plain) => plain.clone(),
-                SectionElem::Content => body_vec.join(""),
+                SectionElem::Content => content.clone(),
                 SectionElem::Level => self.slug.to_string(),
-                SectionElem::Title => self.title.clone(),
+                SectionElem::Title => title.clone(),
                 SectionElem::HeadingNumbering => numbering.clone(),
             };
             embed_article_body.push(str);
         }
-        let mut replacements: Vec<(&str, &str)> = self
-            .variables
+        let variables = with_defaults(&self.variables, &self.declared);
+        let mut replacements: Vec<(&str, &str)> = variables
             .iter()
             .map(|(key, value)| (key.as_str(), value.as_str()))
             .collect();
@@ -335,6 +420,8 @@ pub struct Pending<'c> {
     pub embed_sidebar_data: SidebarData,
     pub embeds: Vec<EmbedData<'c>>,
     pub anchors: &'c Vec<AnchorData>,
+    // Invalid embeds, reported as errors of the article
+    pub errors: Vec<String>,
 }
 
 impl<'c> Pending<'c> {
@@ -346,6 +433,7 @@ impl<'c> Pending<'c> {
         embed_sidebar_data: SidebarData,
         embeds: Vec<EmbedData<'c>>,
         anchors: &'c Vec<AnchorData>,
+        errors: Vec<String>,
     ) -> Self {
         Self {
             raw,
@@ -355,6 +443,7 @@ impl<'c> Pending<'c> {
             embed_sidebar_data,
             embeds,
             anchors,
+            errors,
         }
     }
     pub fn based_on(
@@ -365,6 +454,29 @@ impl<'c> Pending<'c> {
         base_numbering: Option<&Pos>,
         sidebar_type: SidebarType,
         section_type: SectionType,
+    ) -> (Vec<String>, Vec<String>, Vec<String>) {
+        self.based_on_section(
+            config,
+            global_data,
+            base_anchor,
+            base_numbering,
+            sidebar_type,
+            section_type,
+            None,
+        )
+    }
+
+    // Positions in the section are taken relative to it,
+    // so that the section is numbered as the root when embedded alone
+    pub fn based_on_section(
+        &self,
+        config: &TypsiteConfig,
+        global_data: &'c GlobalData<'_, '_, 'c>,
+        base_anchor: Option<&Pos>,
+        base_numbering: Option<&Pos>,
+        sidebar_type: SidebarType,
+        section_type: SectionType,
+        section: Option<&Pos>,
     ) -> (Vec<String>, Vec<String>, Vec<String>) {
         let (mut body, mut full_sidebar, mut embed_sidebar) = self.raw.clone();
         let style = self.style;
@@ -375,6 +487,7 @@ impl<'c> Pending<'c> {
                 base_anchor,
                 base_numbering,
                 style,
+                section,
                 &mut body,
             );
         }
@@ -392,6 +505,7 @@ impl<'c> Pending<'c> {
             base_numbering,
             section_type,
             style,
+            section,
             sidebar,
         );
 
@@ -402,6 +516,7 @@ impl<'c> Pending<'c> {
                 base_anchor,
                 base_numbering,
                 style,
+                section,
                 &mut body,
                 sidebar,
                 sidebar_type,
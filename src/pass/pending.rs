@@ -1,11 +1,13 @@
 use crate::compile::registry::Key;
+use crate::ir::article::Article;
 use crate::ir::article::data::GlobalData;
 use crate::ir::article::dep::Indexes;
 use crate::ir::article::sidebar::{Pos, Sidebar, SidebarIndexes};
 use crate::ir::embed::Embed;
+use crate::ir::embed::variable::{check_variables, merge_declarations};
 use crate::ir::pending::{
-    BodyNumberingData, EmbedData, Pending, SidebarAnchorData, SidebarData, SidebarIndexesData,
-    SidebarNumberingData,
+    BodyNumberingData, EmbedData, EmbedSection, Pending, SidebarAnchorData, SidebarData,
+    SidebarIndexesData, SidebarNumberingData,
 };
 use std::collections::HashMap;
 
@@ -34,10 +36,10 @@ impl<'c, 'b: 'c, 'a: 'b> PendingPass<'a, 'b, 'c> {
         }
     }
 
-    fn emit_embeds(&self, embeds: Vec<&Embed>) -> Vec<EmbedData<'c>> {
+    fn emit_embeds(&self, embeds: Vec<&Embed>, errors: &mut Vec<String>) -> Vec<EmbedData<'c>> {
         embeds
             .into_iter()
-            .filter_map(|embed| self.emit_embed(embed))
+            .filter_map(|embed| self.emit_embed(embed, errors))
             .collect()
     }
 
@@ -91,7 +93,7 @@ impl<'c, 'b: 'c, 'a: 'b> PendingPass<'a, 'b, 'c> {
         SidebarData::new(indexes, numberings, anchors)
     }
 
-    fn emit_embed(&self, embed: &Embed) -> Option<EmbedData<'c>> {
+    fn emit_embed(&self, embed: &Embed, errors: &mut Vec<String>) -> Option<EmbedData<'c>> {
         let slug = embed.slug.clone();
         let child = self.global_data.article(slug.as_str());
         if child.is_none() {
@@ -103,7 +105,40 @@ impl<'c, 'b: 'c, 'a: 'b> PendingPass<'a, 'b, 'c> {
             return None;
         }
         let child = child.unwrap();
+        let section = match &embed.section {
+            Some(pos) => match child.get_body().sections.get(pos) {
+                Some(&body) => Some(EmbedSection::new(
+                    pos.clone(),
+                    body,
+                    child.get_full_sidebar().section(pos),
+                    excluded_embeds(child, pos),
+                )),
+                None => {
+                    eprintln!(
+                        "[WARN] (emit_embed) Section {} of `{}` not found in {}",
+                        pos.iter()
+                            .map(|it| (it + 1).to_string())
+                            .collect::<Vec<_>>()
+                            .join("."),
+                        slug.as_str(),
+                        self.slug
+                    );
+                    return None;
+                }
+            },
+            None => None,
+        };
         let child_metadata = child.get_metadata();
+        let template = embed.template.clone();
+        let declared = merge_declarations(
+            &child.get_meta_options().embed_variables,
+            &self.global_data.config.embed.template(template.as_deref()).variables,
+        );
+        if let Err(err) = check_variables(&embed.variables, &declared) {
+            errors.push(format!("Embed `{}`: {err}", slug.as_str()));
+            return None;
+        }
+        let declared = declared.into_iter().cloned().collect();
         let child_pending = child.get_pending_or_init(self.global_data);
         let section_type = embed.section_type;
         let pos: Pos = embed.sidebar_pos.0.clone();
@@ -124,10 +159,13 @@ impl<'c, 'b: 'c, 'a: 'b> PendingPass<'a, 'b, 'c> {
             embed_sidebar_indexes,
             open,
             variables,
+            template,
+            declared,
             title,
             full_sidebar_title_indexes,
             embed_sidebar_title_indexes,
             child_pending,
+            section,
         ))
     }
 
@@ -143,7 +181,8 @@ impl<'c, 'b: 'c, 'a: 'b> PendingPass<'a, 'b, 'c> {
         let embed_sidebar = article.get_embed_sidebar();
         let full_sidebar_data = self.emit_sidebar(full_sidebar);
         let embed_sidebar_data = self.emit_sidebar(embed_sidebar);
-        let embeds = self.emit_embeds(embeds);
+        let mut errors = Vec::new();
+        let embeds = self.emit_embeds(embeds, &mut errors);
         let anchors = article.get_anchors();
         Pending::new(
             content,
@@ -153,6 +192,17 @@ impl<'c, 'b: 'c, 'a: 'b> PendingPass<'a, 'b, 'c> {
             embed_sidebar_data,
             embeds,
             anchors,
+            errors,
         )
     }
 }
+
+// Entries in the embed sidebar of the embeds out of the section at `pos`
+fn excluded_embeds(article: &Article, pos: &Pos) -> SidebarIndexes {
+    article
+        .get_embeds()
+        .iter()
+        .filter(|embed| !embed.sidebar_pos.0.starts_with(pos))
+        .flat_map(|embed| embed.embed_sidebar_indexes.iter().copied())
+        .collect()
+}
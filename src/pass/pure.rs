@@ -3,10 +3,12 @@ use crate::config::TypsiteConfig;
 use crate::config::rewrite::TagRewriteRule;
 use crate::config::schema::Schema;
 use crate::ir::article::Article;
-use crate::ir::article::body::Body;
+use crate::ir::article::body::{Body, BodySection};
+use crate::ir::article::citation::{Citation, citation_context};
 use crate::ir::article::dep::{Dependency, Source, UpdatedIndex};
 use crate::ir::article::sidebar::{Pos, Sidebar, SidebarPos};
-use crate::ir::embed::{EmbedVariables, SectionType};
+use crate::ir::embed::variable::EmbedVariable;
+use crate::ir::embed::{EmbedVariables, SectionType, parse_section};
 use crate::ir::pending::{AnchorData, AnchorKind};
 use crate::ir::rewriter::RewriterType;
 use crate::pass::pure::body::BodyBuilder;
@@ -18,6 +20,7 @@ use crate::pass::pure::sidebar::PureSidebarBuilder;
 use crate::util::html::write_token;
 use crate::util::html::{Attributes, expect_start};
 use crate::util::path::resolve_path;
+use crate::util::pos_slug;
 use crate::util::str::SectionElem;
 use anyhow::*;
 use html5gum::{StringReader, Tokenizer as HtmlTokenizer};
@@ -60,14 +63,17 @@ pub struct PurePass<'a, 'k> {
     buffer: String,
     content_buffer: Vec<String>, // for heading / metadata
     // heading & sidebar
-    heading_level_backtrace: Vec<usize>,
+    heading_level_backtrace: Vec<(usize, Pos)>,
     sidebar: PureSidebarBuilder,
     sidebar_pos: Option<(Pos, usize)>,
     numberings: HashMap<Pos, usize>,
+    sections: HashMap<Pos, BodySection>,
     // anchors
     anchors: HashMap<String, HashMap<AnchorKind, HashSet<usize>>>,
     // rewriter
     rewriter_backtrace: Vec<Option<HashMap<String, String>>>,
+    // citations (cited slug, body index, heading pos)
+    citations: Vec<(Key, usize, Pos)>,
 }
 
 pub struct PurePassData<'a> {
@@ -83,7 +89,9 @@ pub struct PurePassData<'a> {
     // metadata
     pub metadata: MetadataBuilder<'a>,
     pub numberings: HashMap<Pos, usize>,
+    pub sections: HashMap<Pos, BodySection>,
     pub anchors: HashMap<String, HashMap<AnchorKind, HashSet<usize>>>,
+    pub citations: Vec<(Key, usize, Pos)>,
     //sidebar
     pub sidebar: PureSidebarBuilder,
 }
@@ -113,7 +121,9 @@ impl<'a, 'k> PurePassData<'a> {
             sidebar: pure_pass.sidebar,
             metadata: pure_pass.metadata,
             numberings: pure_pass.numberings,
+            sections: pure_pass.sections,
             anchors: pure_pass.anchors,
+            citations: pure_pass.citations,
         };
         (head, body, error, cache, data)
     }
@@ -157,6 +167,28 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             &mut |pos| full_sidebar.titles.remove(&pos).unwrap(),
             &mut |pos| embed_sidebar.titles.remove(&pos).unwrap(),
         );
+        let citations = data
+            .citations
+            .into_iter()
+            .map(|(cited, body_index, pos)| {
+                let index = body_index.min(body_content.len());
+                let context = citation_context(
+                    &body_content[..index].concat(),
+                    &body_content[index..].concat(),
+                );
+                let anchor = if pos.is_empty() {
+                    String::new()
+                } else {
+                    pos_slug(&pos, &slug)
+                };
+                Citation {
+                    slug: cited.to_string(),
+                    body_index,
+                    anchor,
+                    context,
+                }
+            })
+            .collect::<Vec<_>>();
         let body_numberings = data.numberings;
         let full_sidebar_show_children = full_sidebar.show_children;
         let full_sidebar_numberings = full_sidebar.numberings;
@@ -166,13 +198,14 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
         let embed_sidebar_anchors = embed_sidebar.anchors;
         let dependency = Dependency::new(data.dependency);
         let used_rules = data.used_rules;
-        let body = Body::new(body_content, body_rewriters, body_numberings);
+        let body = Body::new(body_content, body_rewriters, body_numberings, data.sections);
         let full_sidebar = Sidebar::new(
             full_sidebar.contents,
             full_sidebar.titles.remove(&TITLE_POS).unwrap_or_default(),
             full_sidebar_show_children,
             full_sidebar_numberings,
             full_sidebar_anchors,
+            full_sidebar.sections,
         );
         let embed_sidebar = Sidebar::new(
             embed_sidebar.contents,
@@ -180,6 +213,7 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             embed_show_children,
             embed_sidebar_numberings,
             embed_sidebar_anchors,
+            embed_sidebar.sections,
         );
         let anchors = data
             .anchors
@@ -203,6 +237,7 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             dependency,
             used_rules,
             anchors,
+            citations,
         ))
     }
 
@@ -224,8 +259,8 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             Self::handle_body_end_tag,
             Self::push_body_buffer,
         )?;
-        while let Some(level) = self.heading_level_backtrace.pop() {
-            self.push_section_end(level);
+        while let Some((level, pos)) = self.heading_level_backtrace.pop() {
+            self.push_section_end(level, pos);
         }
         Ok(())
     }
@@ -317,8 +352,10 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             dependency: HashMap::new(),
             content_buffer: Vec::new(),
             numberings: HashMap::new(),
+            sections: HashMap::new(),
             anchors: HashMap::new(),
             rewriter_backtrace: Vec::new(),
+            citations: Vec::new(),
         }
     }
 
@@ -344,6 +381,14 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             HeadTag::Unique => {
                 self.push_head_buffer();
             }
+            HeadTag::Permalink { slug } => {
+                self.metadata.set_permalink(slug);
+                self.skip = Some("permalink".to_string());
+            }
+            HeadTag::Alias { slug } => {
+                self.metadata.add_alias(slug);
+                self.skip = Some("alias".to_string());
+            }
         }
         Ok(())
     }
@@ -363,8 +408,7 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
                 let rule = self
                     .config
                     .rules
-                    .get(tag.as_str())
-                    .with_context(|| format!("No rewrite rule named {tag}"))?;
+                    .get(tag.as_str())?;
                 let tag_name = self.config.rules.rule_name(&tag).unwrap();
                 self.used_rules.insert(tag_name);
 
@@ -383,6 +427,15 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             BodyTag::MetaOption { key, value } => {
                 self.metadata.set_options(key, value);
             }
+            BodyTag::EmbedVariable {
+                name,
+                kind,
+                default,
+            } => {
+                let variable = EmbedVariable::new(name, &kind, default)
+                    .with_context(|| format!("EmbedVariable in {}", self.slug))?;
+                self.metadata.declare_embed_variable(variable);
+            }
             BodyTag::MetaContentSet { key } => {
                 self.metadata.meta_key = Some(key);
             }
@@ -406,10 +459,12 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
                 open,
                 variables,
                 sidebar,
+                section,
+                template,
                 heading_level,
             } => {
                 self.push_section_ends_if_needed(heading_level);
-                self.push_embed(slug, open, variables, sidebar, heading_level)?;
+                self.push_embed(slug, open, variables, sidebar, section, template, heading_level)?;
             }
 
             BodyTag::AnchorGoto { id } => {
@@ -424,6 +479,14 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
                 let pos = self.sidebar.intake_heading(heading_level);
                 let before_title = self.config.section.before_title();
                 self.push_section(heading_level, before_title);
+                let start = self.body.len();
+                self.sections.insert(
+                    pos.clone(),
+                    BodySection {
+                        title: (start, start),
+                        content: (start, start),
+                    },
+                );
                 self.sidebar_pos = Some((pos, 0));
             }
         }
@@ -436,8 +499,7 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
                 let rule = self
                     .config
                     .rules
-                    .get(tag.as_str())
-                    .with_context(|| format!("No rewrite rule named {tag}"))?;
+                    .get(tag.as_str())?;
                 let tag_name = self.config.rules.rule_name(tag.as_str()).unwrap();
                 self.push_rewriter_end(tag_name, rule)?;
             }
@@ -449,8 +511,8 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
                 self.add_anchor(AnchorKind::GotoTail, id.to_string());
             }
             BodyTag::Section { heading_level } => {
-                self.sidebar_pos = None;
-                self.push_section_start(heading_level);
+                let pos = self.sidebar_pos.take().map(|(pos, _)| pos).unwrap_or_default();
+                self.push_section_start(heading_level, pos);
                 let buffer = std::mem::take(&mut self.content_buffer);
                 self.sidebar.add_heading_section(heading_level, buffer);
             }
@@ -460,21 +522,30 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
         Ok(())
     }
 
-    fn push_section_start(&mut self, level: usize) {
+    fn push_section_start(&mut self, level: usize, pos: Pos) {
+        let title_end = self.body.len();
         let before_title = self.config.section.before_content();
         self.push_section(level, before_title);
-        self.heading_level_backtrace.push(level);
+        let content_start = self.body.len();
+        if let Some(section) = self.sections.get_mut(&pos) {
+            section.title.1 = title_end;
+            section.content = (content_start, content_start);
+        }
+        self.heading_level_backtrace.push((level, pos));
     }
-    fn push_section_end(&mut self, level: usize) {
+    fn push_section_end(&mut self, level: usize, pos: Pos) {
+        if let Some(section) = self.sections.get_mut(&pos) {
+            section.content.1 = self.body.len();
+        }
         let after_content = self.config.section.after_content();
         self.push_section(level, after_content);
     }
 
     fn push_section_ends_if_needed(&mut self, current_level: usize) {
-        while let Some(&last_level) = self.heading_level_backtrace.last() {
-            if current_level <= last_level {
-                self.heading_level_backtrace.pop();
-                self.push_section_end(last_level);
+        while let Some((last_level, _)) = self.heading_level_backtrace.last() {
+            if current_level <= *last_level {
+                let (last_level, pos) = self.heading_level_backtrace.pop().unwrap();
+                self.push_section_end(last_level, pos);
             } else {
                 break;
             }
@@ -500,8 +571,9 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
         let slug = self.config.path_to_slug(path.as_path())?;
 
         self.registry.slug(slug.as_str()).with_context(|| {
+            let suggestion = self.registry.did_you_mean(&slug);
             format!(
-                "Failed to resolve slug {slug_path} in {}  ({tag})",
+                "Failed to resolve slug {slug_path} in {}  ({tag}){suggestion}",
                 self.slug
             )
         })
@@ -582,6 +654,9 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
         if let Some(path) = rule.path.as_ref() {
             self.depend_path(Source::Path(path.clone()))
         }
+        for file in &rule.files {
+            self.depend_path(Source::Path(file.clone()))
+        }
         dependents.into_iter().for_each(|it| self.depend_path(it));
         Ok(())
     }
@@ -625,6 +700,8 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
         open: bool,
         variables: EmbedVariables,
         sidebar: String,
+        section: Option<String>,
+        template: Option<String>,
         heading_level: usize,
     ) -> Result<()> {
         let slug = self.resolve_slug(&url, "Embed");
@@ -644,6 +721,23 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             ));
         }
 
+        let section = match section.filter(|it| !it.is_empty()) {
+            Some(section) => Some(parse_section(&section).with_context(|| {
+                format!("Invalid section `{section}` of embedding {slug} into {}, expect a heading anchor or numbering like `2.1`", self.slug)
+            })?),
+            None => None,
+        };
+
+        let template = template.filter(|it| !it.is_empty());
+        if let Some(template) = &template
+            && !self.config.embed.has_template(template)
+        {
+            return Err(anyhow!(
+                "Embed template `{template}` not found in components/embeds/, skip embedding {slug} into {}",
+                self.slug
+            ));
+        }
+
         let body_index = self.body.len();
         let section_type = SectionType::from(sidebar);
 
@@ -658,12 +752,23 @@ impl<'a, 'b, 'c, 'k> PurePass<'a, 'k> {
             full_sidebar_pos,
             embed_sidebar_pos,
             section_type,
+            section,
+            template,
             body_index,
         ));
         self.depend_embed(slug);
         Ok(())
     }
 
+    // Where the citation sits, to show it as the context of the backlink
+    pub fn add_citation(&mut self, slug: Key) {
+        if self.metadata.meta_key.is_some() {
+            return;
+        }
+        let pos = self.sidebar.current_full_pos();
+        self.citations.push((slug, self.body.len(), pos));
+    }
+
     pub fn add_footnote(&mut self, name: String) -> (String, usize) {
         self.footnotes.add_footnote(name)
     }
@@ -1,5 +1,5 @@
 use crate::compile::registry::Key;
-use crate::ir::article::sidebar::{SidebarIndexes, SidebarPos};
+use crate::ir::article::sidebar::{Pos, SidebarIndexes, SidebarPos};
 use crate::ir::embed::{Embed, EmbedVariables, SectionType};
 
 pub struct EmbedBuilder {
@@ -9,6 +9,8 @@ pub struct EmbedBuilder {
     full_sidebar_pos: SidebarPos,
     embed_sidebar_pos: SidebarPos,
     section_type: SectionType,
+    section: Option<Pos>,
+    template: Option<String>,
     body_index: usize,
 }
 
@@ -20,6 +22,8 @@ impl EmbedBuilder {
         full_sidebar_pos: SidebarPos,
         embed_sidebar_pos: SidebarPos,
         section_type: SectionType,
+        section: Option<Pos>,
+        template: Option<String>,
         body_index: usize,
     ) -> Self {
         Self {
@@ -29,6 +33,8 @@ impl EmbedBuilder {
             full_sidebar_pos,
             embed_sidebar_pos,
             section_type,
+            section,
+            template,
             body_index,
         }
     }
@@ -45,6 +51,8 @@ impl EmbedBuilder {
             self.open,
             self.variables,
             self.section_type,
+            self.section,
+            self.template,
             self.full_sidebar_pos,
             full_index,
             embed_index,
@@ -1,8 +1,10 @@
+use crate::compile::options::relation::Relation;
 use crate::compile::proj_options;
 use crate::compile::registry::Key;
 use crate::ir::article::sidebar::{HeadingNumberingStyle, SidebarType};
+use crate::ir::embed::variable::EmbedVariable;
 use crate::ir::metadata::Metadata;
-use crate::ir::metadata::content::{MetaContent, MetaContents};
+use crate::ir::metadata::content::{ALIASES_KEY, MetaContent, MetaContents, PERMALINK_KEY};
 use crate::ir::metadata::graph::MetaNode;
 use crate::ir::metadata::options::MetaOptions;
 use crate::ir::rewriter::MetaRewriter;
@@ -17,10 +19,16 @@ pub struct MetadataBuilder<'a> {
     contents: HashMap<String, MetaContent<'a>>,
     pub(crate) heading_numbering_style: HeadingNumberingStyle,
     pub(crate) sidebar_type: SidebarType,
+    pub(crate) pdf: bool,
+    embed_variables: Vec<EmbedVariable>,
     // slugs
     parent: Option<Key>,
     cited: HashSet<Key>,
     children: HashSet<Key>,
+    relations: HashMap<String, HashSet<Key>>,
+    // permalink & aliases from head tags
+    permalink: Option<String>,
+    aliases: Vec<String>,
 }
 
 impl<'a> MetadataBuilder<'a> {
@@ -29,6 +37,7 @@ impl<'a> MetadataBuilder<'a> {
 
         let heading_numbering_style = options.default_metadata.options.heading_numbering;
         let sidebar_type = options.default_metadata.options.sidebar_type;
+        let pdf = options.pdf.enable;
         let parent = None;
         // options
         // .default_metadata
@@ -50,9 +59,14 @@ impl<'a> MetadataBuilder<'a> {
             contents: HashMap::new(),
             heading_numbering_style,
             sidebar_type,
+            pdf,
+            embed_variables: Vec::new(),
             parent,
             cited: HashSet::new(),
             children: HashSet::new(),
+            relations: HashMap::new(),
+            permalink: None,
+            aliases: Vec::new(),
         }
     }
 
@@ -74,8 +88,16 @@ impl<'a> MetadataBuilder<'a> {
             "child" => {
                 self.children.insert(slug);
             }
-            _ => {
-                eprintln!("[WARN] MetadataBuilder: Unknown metadata graph kind: {kind}");
+            kind => {
+                let options = proj_options().unwrap();
+                if Relation::find(&options.relations, kind).is_some() {
+                    self.relations
+                        .entry(kind.to_string())
+                        .or_default()
+                        .insert(slug);
+                } else {
+                    eprintln!("[WARN] MetadataBuilder: Unknown metadata graph kind: {kind}");
+                }
             }
         }
     }
@@ -88,12 +110,28 @@ impl<'a> MetadataBuilder<'a> {
             "sidebar" => {
                 self.sidebar_type = SidebarType::from(value.as_ref());
             }
+            "pdf" => {
+                self.pdf = value != "false";
+            }
             _ => {
                 eprintln!("[WARN] Unknown metadata option: {key}");
             }
         }
     }
 
+    pub fn declare_embed_variable(&mut self, variable: EmbedVariable) {
+        self.embed_variables.retain(|it| it.name != variable.name);
+        self.embed_variables.push(variable);
+    }
+
+    pub fn set_permalink(&mut self, permalink: String) {
+        self.permalink = Some(permalink);
+    }
+
+    pub fn add_alias(&mut self, alias: String) {
+        self.aliases.push(alias);
+    }
+
     pub fn push_rewriter(&mut self, builder: &RewriterBuilder<'a>) {
         self.meta_rewriter_buffer.push(builder.build_meta());
     }
@@ -114,10 +152,28 @@ impl<'a> MetadataBuilder<'a> {
     }
 
     pub(crate) fn build(
-        self,
+        mut self,
         slug: Key,
         cache: Option<&MetaContents<'a>>,
     ) -> anyhow::Result<Metadata<'a>> {
+        // Head tags take precedence over meta contents
+        if let Some(permalink) = self.permalink.take() {
+            let content = MetaContent::new(vec![permalink], Vec::new());
+            self.contents.insert(PERMALINK_KEY.to_string(), content);
+        }
+        if !self.aliases.is_empty() {
+            let aliases = self
+                .contents
+                .remove(ALIASES_KEY)
+                .map(|content| vec![content.get().to_string()])
+                .unwrap_or_default()
+                .into_iter()
+                .chain(self.aliases.drain(..))
+                .collect::<Vec<_>>()
+                .join(",");
+            let content = MetaContent::new(vec![aliases], Vec::new());
+            self.contents.insert(ALIASES_KEY.to_string(), content);
+        }
         let updated = cache
             .as_ref()
             .map(|cache| !cache.same_contents(&self.contents))
@@ -126,6 +182,8 @@ impl<'a> MetadataBuilder<'a> {
         let options = MetaOptions {
             heading_numbering_style: self.heading_numbering_style,
             sidebar_type: self.sidebar_type,
+            pdf: self.pdf,
+            embed_variables: self.embed_variables,
         };
         let node = MetaNode {
             slug,
@@ -134,6 +192,8 @@ impl<'a> MetadataBuilder<'a> {
             backlinks: HashSet::new(),
             references: self.cited,
             children: self.children,
+            relations: self.relations,
+            inverse_relations: HashMap::new(),
         };
         Ok(Metadata {
             contents,
@@ -49,6 +49,7 @@ pub struct SidebarData<'a> {
     pub numberings: HashMap<Pos, SidebarIndexes>,
     pub anchors: HashMap<Pos, SidebarIndexes>,
     pub titles: HashMap<SidebarPos, SidebarIndexes>,
+    pub sections: HashMap<Pos, (usize, usize)>,
 }
 
 impl<'a> SidebarData<'a> {
@@ -76,6 +77,7 @@ impl<'a> SidebarData<'a> {
     }
 
     fn intake(&mut self, section: Section) {
+        let start = self.contents.len();
         match section.data {
             SectionData::Inner {
                 level: _level,
@@ -106,6 +108,8 @@ impl<'a> SidebarData<'a> {
                         }
                     }
                 }
+                self.sections
+                    .insert(self.pos.clone(), (start, self.contents.len()));
             }
             SectionData::Embed => {
                 let index = self.contents.len();
@@ -131,6 +135,7 @@ impl<'a> SidebarData<'a> {
             numberings: HashMap::new(),
             anchors: HashMap::new(),
             titles: HashMap::new(),
+            sections: HashMap::new(),
         };
         builder.intake(section);
         builder
@@ -14,6 +14,11 @@ mod cite;
 mod code;
 mod footnote;
 mod metacontent;
+mod process;
+mod template;
+
+pub use code::clean_highlight_cache;
+pub use code::highlighter::theme_css;
 
 pub const METACONTENT_TAG: &str = "metacontent";
 
@@ -76,6 +81,11 @@ pub trait TagRewritePass: Id + Atom + Send + Sync + Purity {
         Ok(HashSet::default())
     }
 
+    // Head added to the pages using the pass, besides the head of the rule
+    fn head(&self) -> Option<&'static str> {
+        None
+    }
+
     // If pure, it can pass HTML without MetaData
     fn pure_start(
         &self,
@@ -128,14 +138,11 @@ impl<'c, 'b: 'c, 'a: 'b> RewritePass<'a, 'b, 'c> {
     ) -> Option<String> {
         let rewriter = self.global_data.config.rules.get(rewriter_id);
         match rewriter {
-            None => {
-                eprintln!(
-                    "[WARN] Rewriter `{}` not found in {}",
-                    rewriter_id, self.slug
-                );
+            Err(err) => {
+                eprintln!("[WARN] {err} in {}", self.slug);
                 Some(format!("<< Rewriter `{rewriter_id}` not found >>"))
             }
-            Some(rewriter) => {
+            std::result::Result::Ok(rewriter) => {
                 if rewriter.pass.pure() {
                     rewriter.pure_start(attributes, self.global_data.config)
                 } else {
@@ -152,14 +159,11 @@ impl<'c, 'b: 'c, 'a: 'b> RewritePass<'a, 'b, 'c> {
     ) -> Option<String> {
         let rewriter = self.global_data.config.rules.get(rewriter_id);
         match rewriter {
-            None => {
-                eprintln!(
-                    "[WARN] Rewriter `{}` not found in {}",
-                    rewriter_id, self.slug
-                );
+            Err(err) => {
+                eprintln!("[WARN] {err} in {}", self.slug);
                 Some(format!("<< Rewriter `{rewriter_id}` not found >>"))
             }
-            Some(rewriter) => {
+            std::result::Result::Ok(rewriter) => {
                 if rewriter.pass.pure() {
                     rewriter.pure_end(attributes, self.global_data.config)
                 } else {
@@ -19,6 +19,7 @@ impl TagRewritePass for CitePass {
         let slug = slug.unwrap();
         let slug = pass.resolve_slug(slug.as_str(), "Cite")?;
         pass.metadata.add_cite(slug.clone());
+        pass.add_citation(slug.clone());
         let anchor = attrs
             .get("anchor")
             .map(|s| s.to_string())
@@ -70,12 +71,13 @@ fn cite<'c, 'b: 'c, 'a: 'b>(
     global_data: &'c GlobalData<'a, 'b, 'c>,
     text: &str,
 ) -> Option<String> {
-    let url = if anchor.is_empty() {
-        slug.to_string()
-    } else {
-        format!("{slug}#{anchor}")
-    };
-    global_data
-        .metadata(slug)
-        .map(|metadata| metadata.inline_with(text, &[("{url}", url.as_str())]))
+    global_data.metadata(slug).map(|metadata| {
+        let slug = metadata.contents.public_slug();
+        let url = if anchor.is_empty() {
+            slug
+        } else {
+            format!("{slug}#{anchor}")
+        };
+        metadata.inline_with(text, &[("{url}", url.as_str())])
+    })
 }
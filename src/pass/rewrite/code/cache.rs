@@ -0,0 +1,83 @@
+use crate::util::error::log_err;
+use crate::util::fs::{prune_cache_dir, read_cache_entry, write_into_file};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+pub const HIGHLIGHT_CACHE_DIR: &str = "highlight";
+// Bumped whenever the highlighted HTML changes for the same inputs
+const VERSION: &str = "1";
+// Highlights not used for this long are removed from the cache
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Highlighted {
+    pub light: String,
+    pub dark: String,
+}
+
+// Key of a highlight, by everything the output depends on
+pub fn cache_key(parts: &[&str]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(VERSION.as_bytes());
+    for part in parts {
+        hasher.update(&[0]);
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+// Reuse the highlight cached in `dir` by the key, or highlight and cache it
+pub fn cached(
+    dir: &Path,
+    key: &str,
+    highlight: impl FnOnce() -> Option<Highlighted>,
+) -> Option<Highlighted> {
+    let path = dir.join(key).with_extension("json");
+    let cached = read_cache_entry(&path).and_then(|json| serde_json::from_str(&json).ok());
+    if cached.is_some() {
+        return cached;
+    }
+    let highlighted = highlight()?;
+    if let Ok(json) = serde_json::to_string(&highlighted) {
+        log_err(write_into_file(path, &json, "highlight cache"));
+    }
+    Some(highlighted)
+}
+
+// Remove the highlights left unused for long,
+// the ones of old themes, syntaxes or options are never hit again as they're in the key
+pub fn clean_highlight_cache(cache_path: &Path) {
+    prune_cache_dir(cache_path.join(HIGHLIGHT_CACHE_DIR), MAX_AGE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_cached_highlight() {
+        let cache_path = std::env::temp_dir().join("typsite-highlight-cache-test");
+        let _ = std::fs::remove_dir_all(&cache_path);
+        let dir = cache_path.join(HIGHLIGHT_CACHE_DIR);
+        let key = cache_key(&["fn main() {}", "Rust", "onedark"]);
+        assert_ne!(key, cache_key(&["fn main() {}", "Rust", "github"]));
+        // Parts are separated, so that moving a boundary changes the key
+        assert_ne!(cache_key(&["ab", "c"]), cache_key(&["a", "bc"]));
+
+        let highlighted = || Highlighted {
+            light: "light".to_string(),
+            dark: "dark".to_string(),
+        };
+        assert_eq!(
+            cached(&dir, &key, || Some(highlighted())),
+            Some(highlighted())
+        );
+        assert_eq!(cached(&dir, &key, || None), Some(highlighted()));
+
+        // Recently used highlights are kept
+        clean_highlight_cache(&cache_path);
+        assert_eq!(cached(&dir, &key, || None), Some(highlighted()));
+        let _ = std::fs::remove_dir_all(&cache_path);
+    }
+}
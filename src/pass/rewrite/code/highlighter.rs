@@ -1,54 +1,374 @@
-
-use syntect::highlighting::{HighlightIterator, HighlightState, Highlighter, Theme};
-use syntect::html::{IncludeBackground, append_highlighted_html_for_styled_line};
-use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+use crate::util::html::escape_xml;
+use anyhow::{Context, Result};
+use syntect::highlighting::{
+    Color, FontStyle, HighlightIterator, HighlightState, Highlighter, Theme,
+};
+use syntect::html::{
+    ClassStyle, IncludeBackground, append_highlighted_html_for_styled_line,
+    line_tokens_to_classed_spans,
+};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
 use syntect::util::LinesWithEndings;
 
+// Prefix of the classes of classed highlighting, to avoid collisions with the site's classes
+const CLASS_PREFIX: &str = "hl-";
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: CLASS_PREFIX,
+};
+
+// Options of a code block, lines are wrapped only if any of them is set
+#[derive(Debug, Default, PartialEq)]
+pub struct CodeOptions {
+    pub line_numbers: bool,
+    // Number of the first line
+    pub start: usize,
+    // Inclusive ranges of the highlighted lines, by line number
+    pub highlighted: Vec<(usize, usize)>,
+    // Marks lines starting with `+` / `-`
+    pub diff: bool,
+}
+
+impl CodeOptions {
+    pub fn new(
+        line_numbers: bool,
+        start: usize,
+        highlighted: Vec<(usize, usize)>,
+        diff: bool,
+    ) -> Self {
+        Self {
+            line_numbers,
+            start,
+            highlighted,
+            diff,
+        }
+    }
+
+    fn wrap_lines(&self) -> bool {
+        self.line_numbers || self.diff || !self.highlighted.is_empty()
+    }
+
+    fn open_line(&self, output: &mut String, number: usize, text: &str) {
+        output.push_str(&format!(
+            "<span class=\"{}\">",
+            self.line_class(number, text)
+        ));
+        if self.line_numbers {
+            output.push_str(&format!(
+                "<span class=\"line-number\" data-line=\"{number}\"></span>"
+            ));
+        }
+    }
+
+    fn line_class(&self, number: usize, line: &str) -> String {
+        let mut class = String::from("line");
+        if self
+            .highlighted
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&number))
+        {
+            class.push_str(" highlighted");
+        }
+        if self.diff {
+            if line.starts_with('+') {
+                class.push_str(" diff-add");
+            } else if line.starts_with('-') {
+                class.push_str(" diff-del");
+            }
+        }
+        class
+    }
+}
+
+// Line ranges like "1,3-5"
+pub fn parse_line_ranges(ranges: &str) -> Result<Vec<(usize, usize)>> {
+    ranges
+        .split(',')
+        .map(str::trim)
+        .filter(|range| !range.is_empty())
+        .map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let start = start.trim().parse::<usize>();
+            let end = end.trim().parse::<usize>();
+            match (start, end) {
+                (Ok(start), Ok(end)) if start <= end => Ok((start, end)),
+                _ => None
+                    .with_context(|| format!("Invalid line range `{range}`, expect like `1,3-5`")),
+            }
+        })
+        .collect()
+}
+
 pub fn highlight(
     syntax_set: &SyntaxSet,
     syntax: &SyntaxReference,
     content: &str,
     theme: &Theme,
     fallback_color: &str,
+    options: &CodeOptions,
 ) -> String {
     let highlighter = Highlighter::new(theme);
     let scope_stack = ScopeStack::new();
     let mut highlight_state = HighlightState::new(&highlighter, scope_stack);
 
     let mut output = String::new();
+    let wrap_lines = options.wrap_lines();
 
     // Init the parser
     let mut parse_state = ParseState::new(syntax);
     // Iterate over the lines of the content
-    for line in LinesWithEndings::from(content) {
+    for (index, line) in LinesWithEndings::from(content).enumerate() {
+        let number = options.start + index;
+        let (text, ending) = split_line(line, wrap_lines);
+        if wrap_lines {
+            options.open_line(&mut output, number, text);
+        }
         // Try to parse the line
-        let ops = match parse_state.parse_line(line, syntax_set) {
-            Ok(ops) => ops,
+        match parse_state.parse_line(line, syntax_set) {
+            Ok(ops) => {
+                let styles = HighlightIterator::new(&mut highlight_state, &ops, line, &highlighter);
+                // Append the highlighted line to the output HTML
+                for (style, piece) in styles {
+                    let piece = if wrap_lines {
+                        piece.trim_end_matches(['\n', '\r'])
+                    } else {
+                        piece
+                    };
+                    append_highlighted_html_for_styled_line(
+                        &[(style, piece)],
+                        IncludeBackground::No,
+                        &mut output,
+                    )
+                    .unwrap();
+                }
+            }
             Err(_) => {
                 //  If the line can't be parsed, reset the parser and apply a fallback style
                 // 1. Reset the parser
                 parse_state = ParseState::new(syntax);
                 // 2. Apply a fallback style
-                output.push_str(&apply_fallback_style(fallback_color, line));
-                continue;
+                output.push_str(&apply_fallback_style(fallback_color, text));
             }
-        };
+        }
+        if wrap_lines {
+            output.push_str("</span>");
+            output.push_str(ending);
+        }
+    }
+    output
+}
 
-        let styles = HighlightIterator::new(&mut highlight_state, &ops, line, &highlighter);
+// Highlight with classes instead of inline styles, which are colored by `theme_css`.
+// Lines are kept balanced by reopening the spans of the scopes left open by the previous line.
+pub fn highlight_classed(
+    syntax_set: &SyntaxSet,
+    syntax: &SyntaxReference,
+    content: &str,
+    options: &CodeOptions,
+) -> String {
+    let mut output = String::new();
+    let wrap_lines = options.wrap_lines();
 
-        // Append the highlighted line to the output HTML
-        for (style, text) in styles {
-            append_highlighted_html_for_styled_line(
-                &[(style, text)],
-                IncludeBackground::No,
-                &mut output,
-            )
-            .unwrap();
+    let mut parse_state = ParseState::new(syntax);
+    let mut scope_stack = ScopeStack::new();
+    for (index, line) in LinesWithEndings::from(content).enumerate() {
+        let number = options.start + index;
+        // Spans are closed before the line ending
+        let (text, ending) = split_line(line, true);
+        if wrap_lines {
+            options.open_line(&mut output, number, text);
+        }
+        let opened = scope_stack.len();
+        for scope in scope_stack.as_slice() {
+            output.push_str(&format!("<span class=\"{}\">", scope_classes(*scope)));
         }
+        let spans = parse_state
+            .parse_line(line, syntax_set)
+            .ok()
+            .and_then(|ops| {
+                // Operations at the line ending apply to the end of the text
+                let ops = ops
+                    .into_iter()
+                    .map(|(index, op)| (index.min(text.len()), op))
+                    .collect::<Vec<_>>();
+                line_tokens_to_classed_spans(text, &ops, CLASS_STYLE, &mut scope_stack).ok()
+            });
+        let closing = match spans {
+            Some((html, delta)) => {
+                output.push_str(&html);
+                opened.saturating_add_signed(delta)
+            }
+            None => {
+                // If the line can't be parsed, reset the parser and leave the line unstyled
+                parse_state = ParseState::new(syntax);
+                scope_stack = ScopeStack::new();
+                output.push_str(&escape_xml(text));
+                opened
+            }
+        };
+        output.push_str(&"</span>".repeat(closing));
+        if wrap_lines {
+            output.push_str("</span>");
+        }
+        output.push_str(ending);
     }
     output
 }
 
+// CSS of the classes of `highlight_classed` for a theme pair,
+// applied to elements under `[data-code-theme="<name>"]` by the viewer's color-scheme
+pub fn theme_css(name: &str, light: &Theme, dark: &Theme) -> String {
+    let mut css = format!("/* {name} */\n");
+    for (scheme, theme) in [("light", light), ("dark", dark)] {
+        let root = format!("[data-code-theme=\"{name}\"]");
+        css.push_str(&format!("@media (prefers-color-scheme: {scheme}) {{\n"));
+        if let Some(color) = theme.settings.foreground {
+            css.push_str(&format!("  {root} {{ color: {}; }}\n", css_color(color)));
+        }
+        for item in &theme.scopes {
+            let selectors = item
+                .scope
+                .selectors
+                .iter()
+                .map(|selector| {
+                    let scopes = selector
+                        .extract_scopes()
+                        .into_iter()
+                        .map(scope_selector)
+                        .collect::<Vec<_>>();
+                    format!("{root} {}", scopes.join(" "))
+                })
+                .collect::<Vec<_>>();
+            let mut declarations = Vec::new();
+            if let Some(color) = item.style.foreground {
+                declarations.push(format!("color: {};", css_color(color)));
+            }
+            if let Some(font_style) = item.style.font_style {
+                if font_style.contains(FontStyle::UNDERLINE) {
+                    declarations.push("text-decoration: underline;".to_string());
+                }
+                if font_style.contains(FontStyle::BOLD) {
+                    declarations.push("font-weight: bold;".to_string());
+                }
+                if font_style.contains(FontStyle::ITALIC) {
+                    declarations.push("font-style: italic;".to_string());
+                }
+            }
+            if selectors.is_empty() || declarations.is_empty() {
+                continue;
+            }
+            css.push_str(&format!(
+                "  {} {{ {} }}\n",
+                selectors.join(", "),
+                declarations.join(" ")
+            ));
+        }
+        css.push_str("}\n");
+    }
+    css
+}
+
+// The line ending is kept out of wrapped lines
+fn split_line(line: &str, wrap_lines: bool) -> (&str, &str) {
+    if wrap_lines {
+        let text = line.trim_end_matches(['\n', '\r']);
+        (text, &line[text.len()..])
+    } else {
+        (line, "")
+    }
+}
+
+fn scope_atoms(scope: Scope) -> Vec<String> {
+    scope
+        .build_string()
+        .split('.')
+        .map(|atom| format!("{CLASS_PREFIX}{atom}"))
+        .collect()
+}
+
+fn scope_classes(scope: Scope) -> String {
+    scope_atoms(scope).join(" ")
+}
+
+fn scope_selector(scope: Scope) -> String {
+    scope_atoms(scope)
+        .iter()
+        .map(|class| format!(".{class}"))
+        .collect()
+}
+
+fn css_color(color: Color) -> String {
+    if color.a == 0xFF {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    } else {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            color.r, color.g, color.b, color.a
+        )
+    }
+}
+
 fn apply_fallback_style(color: &str, text: &str) -> String {
     format!("<span style='color:{color};'>{text}</span>")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syntect::highlighting::ThemeSet;
+
+    #[test]
+    fn wrap_code_lines() {
+        assert_eq!(parse_line_ranges("1, 3-5").unwrap(), vec![(1, 1), (3, 5)]);
+        assert!(parse_line_ranges("5-3").is_err());
+
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let syntax = syntax_set.find_syntax_plain_text();
+        let theme = &ThemeSet::load_defaults().themes["InspiredGitHub"];
+        let content = "a\n+b\n-c\n";
+
+        let plain = highlight(
+            &syntax_set,
+            syntax,
+            content,
+            theme,
+            "",
+            &CodeOptions::default(),
+        );
+        assert!(!plain.contains("class=\"line"));
+
+        let options = CodeOptions::new(true, 10, vec![(11, 11)], true);
+        let html = highlight(&syntax_set, syntax, content, theme, "", &options);
+        let lines = html.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(
+            lines[0]
+                .starts_with("<span class=\"line\"><span class=\"line-number\" data-line=\"10\">")
+        );
+        assert!(lines[1].starts_with("<span class=\"line highlighted diff-add\">"));
+        assert!(lines[2].starts_with("<span class=\"line diff-del\">"));
+        assert!(lines.iter().all(|line| line.ends_with("</span>")));
+    }
+
+    #[test]
+    fn classed_code_lines() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let syntax = syntax_set.find_syntax_by_token("rs").unwrap();
+        // The comment spans across the lines
+        let content = "/* a\nb */ fn\n";
+        let html = highlight_classed(&syntax_set, syntax, content, &CodeOptions::default());
+        assert!(!html.contains("style="));
+        assert!(html.contains("class=\"hl-comment hl-block hl-rust\""));
+        // Each line is balanced
+        for line in html.lines() {
+            assert_eq!(
+                line.matches("<span").count(),
+                line.matches("</span>").count()
+            );
+        }
+
+        let theme = &ThemeSet::load_defaults().themes["InspiredGitHub"];
+        let css = theme_css("github", theme, theme);
+        assert!(css.contains("@media (prefers-color-scheme: dark)"));
+        assert!(css.contains("[data-code-theme=\"github\"] .hl-comment"));
+    }
+}
@@ -1,28 +1,100 @@
+use crate::compile::{compile_options, proj_options};
+use crate::config::TypsiteConfig;
 use crate::pass::pure::PurePass;
-use crate::pass::rewrite::code::highlighter::highlight;
+use crate::pass::rewrite::code::cache::{HIGHLIGHT_CACHE_DIR, Highlighted, cache_key, cached};
+use crate::pass::rewrite::code::highlighter::{
+    CodeOptions, highlight, highlight_classed, parse_line_ranges,
+};
+use crate::pass::rewrite::code::source::SourceRange;
 use crate::pass::rewrite::*;
-use crate::util::html::Attributes;
+use crate::util::html::{Attributes, escape_xml};
+use crate::util::path::{file_ext, resolve_path};
 use crate::util::str::ac_replace;
-use crate::{compile::proj_options, config::TypsiteConfig};
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::result::Result::Ok;
+use std::sync::Arc;
 use typsite_macros::rewrite_pass;
 
+mod cache;
 pub mod highlighter;
+mod source;
+
+pub use cache::clean_highlight_cache;
+
+// The CSS generated for classed highlighting, at `rewrite/code_themes.css` of the assets
+const CODE_THEMES_LINK: &str =
+    r#"<link rel="stylesheet" href="../assets/rewrite/code_themes.css">"#;
 
 rewrite_pass![CodeBlockPass, id = "code", atom = true];
 
 impl TagRewritePass for CodeBlockPass {
-    fn init(&self, mut attrs: Attributes, _: &mut PurePass) -> Result<HashMap<String, String>> {
-        let lang = attrs.take("lang")?;
+    fn init(&self, mut attrs: Attributes, pass: &mut PurePass) -> Result<HashMap<String, String>> {
+        // Code of the block, or the source file it's read from
+        let src = match attrs.take("src") {
+            Ok(src) => Some(
+                resolve_path(pass.root, pass.path.parent().unwrap(), &src)
+                    .with_context(|| format!("CodeBlockPass: invalid src `{src}`"))?,
+            ),
+            Err(_) => None,
+        };
+        let content = match src {
+            Some(_) => attrs.take("content").unwrap_or_default(),
+            None => attrs.take("content")?,
+        };
+        let lines = attrs.take("lines").ok();
+        let region = attrs.take("region").ok();
+        let range = SourceRange::new(lines.as_deref(), region.as_deref())
+            .context("CodeBlockPass: invalid source range")?;
+        if let Some(src) = &src {
+            range.read(src).context("CodeBlockPass: invalid src")?;
+        }
+        // The language is inferred from the extension of the source file if not given
+        let lang = match (attrs.take("lang"), &src) {
+            (Ok(lang), _) => lang,
+            (Err(_), Some(src)) => file_ext(src)
+                .filter(|ext| pass.config.highlight.has_syntax(ext))
+                .unwrap_or("text".into()),
+            (Err(err), None) => return Err(err),
+        };
         let theme = attrs.take("theme").unwrap_or("onedark".into());
-        let content = attrs.take("content")?;
-        Ok([
+        // Options of code blocks
+        let line_numbers = attrs.take("line-numbers").unwrap_or("false".into());
+        // Source files start at the first line of the range by default
+        let start = attrs.take("start").ok();
+        let highlight = attrs.take("highlight").unwrap_or_default();
+        let title = attrs.take("title").unwrap_or_default();
+        let diff = attrs.take("diff").unwrap_or("false".into());
+        if let Some(start) = &start {
+            start
+                .trim()
+                .parse::<usize>()
+                .with_context(|| format!("CodeBlockPass: invalid start line `{start}`"))?;
+        }
+        parse_line_ranges(&highlight).context("CodeBlockPass: invalid highlight")?;
+        let mut attrs: HashMap<String, String> = [
             ("lang".into(), lang.to_string()),
             ("theme".into(), theme.to_string()),
             ("content".into(), content.to_string()),
+            ("line-numbers".into(), line_numbers),
+            ("highlight".into(), highlight),
+            ("title".into(), title),
+            ("diff".into(), diff),
         ]
         .into_iter()
-        .collect())
+        .collect();
+        match (start, &src) {
+            (Some(start), _) => attrs.insert("start".into(), start.trim().to_string()),
+            (None, None) => attrs.insert("start".into(), "1".into()),
+            (None, Some(_)) => None,
+        };
+        if let Some(src) = src {
+            attrs.insert("src".into(), src.to_string_lossy().to_string());
+        }
+        attrs.extend(lines.map(|it| ("lines".into(), it)));
+        attrs.extend(region.map(|it| ("region".into(), it)));
+        Ok(attrs)
     }
 
     fn dependents<'a>(
@@ -31,6 +103,10 @@ impl TagRewritePass for CodeBlockPass {
         pass: &PurePass<'a, '_>,
     ) -> Result<HashSet<Source>> {
         let mut path = HashSet::new();
+        // Editing the source file re-highlights the articles including it
+        if let Some(src) = attrs.get("src") {
+            path.insert(Source::Path(Arc::from(Path::new(src))));
+        }
         let lang = attrs.get("lang").unwrap();
         let config = &pass.config.highlight;
         if !config.is_syntax_by_default(lang) {
@@ -51,6 +127,11 @@ impl TagRewritePass for CodeBlockPass {
         Ok(path)
     }
 
+    fn head(&self) -> Option<&'static str> {
+        let classed = proj_options().is_ok_and(|options| options.code_highlight.classed);
+        classed.then_some(CODE_THEMES_LINK)
+    }
+
     fn pure_start(
         &self,
         attrs: &HashMap<String, String>,
@@ -59,16 +140,114 @@ impl TagRewritePass for CodeBlockPass {
     ) -> Option<String> {
         let lang = attrs.get("lang")?;
         let theme = attrs.get("theme")?;
-        let content = attrs.get("content")?;
+        let (content, first_line) = match source_content(attrs) {
+            Ok(Some((content, first_line))) => (Cow::Owned(content), first_line),
+            Ok(None) => (Cow::Borrowed(attrs.get("content")?), 1),
+            Err(err) => {
+                eprintln!("[WARN] CodeBlockPass: {err:?}");
+                return None;
+            }
+        };
         let syntax = config.highlight.find_syntax(lang);
         let syntax_set = config.highlight.syntax_set(lang);
-        let (light, dark) = config.highlight.find_theme(theme)?;
-        let fallback = &proj_options().unwrap().code_fallback_style;
-        let light = highlight(syntax_set, syntax, content, light, &fallback.light);
-        let dark = highlight(syntax_set, syntax, content, dark, &fallback.dark);
+        let options = code_options(attrs, first_line);
+        let proj_options = proj_options().unwrap();
+        let classed = proj_options.code_highlight.classed;
+        let fallback = &proj_options.code_fallback_style;
+        let highlight_code = || {
+            // Classed code is the same for light & dark
+            if classed {
+                let classed = highlight_classed(syntax_set, syntax, &content, &options);
+                return Some(Highlighted {
+                    light: classed.clone(),
+                    dark: classed,
+                });
+            }
+            let (light, dark) = config.highlight.find_theme(theme)?;
+            Some(Highlighted {
+                light: highlight(
+                    syntax_set,
+                    syntax,
+                    &content,
+                    light,
+                    &fallback.light,
+                    &options,
+                ),
+                dark: highlight(syntax_set, syntax, &content, dark, &fallback.dark, &options),
+            })
+        };
+        // Classed code doesn't depend on the theme
+        let style = if classed {
+            "classed".to_string()
+        } else {
+            let theme_hash = config.highlight.theme_hash(theme)?;
+            format!("{theme_hash}:{}:{}", fallback.light, fallback.dark)
+        };
+        let key = cache_key(&[
+            &content,
+            &syntax.name,
+            config.highlight.syntax_hash(lang),
+            &style,
+            &format!("{options:?}"),
+        ]);
+        let Highlighted { light, dark } = match compile_options() {
+            Ok(compile) => cached(
+                &compile.cache_path.join(HIGHLIGHT_CACHE_DIR),
+                &key,
+                highlight_code,
+            )?,
+            Err(_) => highlight_code()?,
+        };
+        // Without classes, the colors of light & dark are switched by the viewer's color-scheme
+        let content = if classed {
+            light.clone()
+        } else {
+            format!(
+                "<span color-scheme=\"light\">{light}</span><span color-scheme=\"dark\">{dark}</span>"
+            )
+        };
+        // Attributes are escaped, as they're placed in attributes and captions of the rule
+        let get = |key: &str| escape_xml(attrs.get(key).map(String::as_str).unwrap_or_default());
         Some(ac_replace(
             body,
-            &[("{content-light}", &light), ("{content-dark}", &dark)],
+            &[
+                ("{content}", &content),
+                ("{content-light}", &light),
+                ("{content-dark}", &dark),
+                ("{theme}", &escape_xml(theme)),
+                ("{lang}", &escape_xml(lang)),
+                ("{title}", &get("title")),
+                ("{line-numbers}", &get("line-numbers")),
+                ("{start}", &options.start.to_string()),
+                ("{highlight}", &get("highlight")),
+                ("{diff}", &get("diff")),
+            ],
         ))
     }
 }
+
+// Read again on each rewrite, as the source file may have changed since `init`
+fn source_content(attrs: &HashMap<String, String>) -> Result<Option<(String, usize)>> {
+    let Some(src) = attrs.get("src") else {
+        return Ok(None);
+    };
+    let lines = attrs.get("lines").map(String::as_str);
+    let region = attrs.get("region").map(String::as_str);
+    SourceRange::new(lines, region)?
+        .read(Path::new(src))
+        .map(Some)
+}
+
+// Attributes cached before the options were added are taken as unset
+fn code_options(attrs: &HashMap<String, String>, first_line: usize) -> CodeOptions {
+    let flag = |key: &str| attrs.get(key).is_some_and(|it| it == "true");
+    let start = attrs
+        .get("start")
+        .and_then(|it| it.parse().ok())
+        .unwrap_or(first_line);
+    let highlighted = attrs
+        .get("highlight")
+        .and_then(|it| parse_line_ranges(it).ok())
+        .unwrap_or_default();
+    CodeOptions::new(flag("line-numbers"), start, highlighted, flag("diff"))
+}
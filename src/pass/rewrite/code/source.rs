@@ -0,0 +1,156 @@
+use crate::pass::rewrite::code::highlighter::parse_line_ranges;
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+
+const END_REGION: &str = "endregion";
+const REGION: &str = "region:";
+// Markers are whole-line comments started by one of these
+const COMMENT_TOKENS: [&str; 7] = ["//", "#", "--", "<!--", "/*", ";", "%"];
+
+// Part of a source file shown by a code block, the whole file if neither is set
+#[derive(Debug, Default)]
+pub struct SourceRange<'a> {
+    // An inclusive line range, like "10-20"
+    pub lines: Option<&'a str>,
+    // A region between `region: <name>` and `endregion` marker comments
+    pub region: Option<&'a str>,
+}
+
+impl<'a> SourceRange<'a> {
+    pub fn new(lines: Option<&'a str>, region: Option<&'a str>) -> Result<Self> {
+        if lines.is_some() && region.is_some() {
+            bail!("Expect either `lines` or `region`, not both");
+        }
+        Ok(Self { lines, region })
+    }
+
+    // The content of the range, and the number of its first line
+    pub fn read(&self, path: &Path) -> Result<(String, usize)> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read source file {path:?}"))?;
+        self.extract(&source)
+            .with_context(|| format!("In source file {path:?}"))
+    }
+
+    fn extract(&self, source: &str) -> Result<(String, usize)> {
+        let lines = source.lines().collect::<Vec<_>>();
+        if let Some(range) = self.lines {
+            let (start, end) = match parse_line_ranges(range)?.as_slice() {
+                [range] => *range,
+                _ => bail!("Invalid line range `{range}`, expect like `10-20`"),
+            };
+            if start == 0 || end > lines.len() {
+                bail!("Line range `{range}` is out of 1-{}", lines.len());
+            }
+            return Ok((join(&lines[start - 1..end]), start));
+        }
+        if let Some(name) = self.region {
+            let begin = lines
+                .iter()
+                .position(|line| region_name(line) == Some(name))
+                .with_context(|| format!("Region `{name}` not found"))?;
+            // Regions may nest, markers of the inner ones are kept to match line numbers
+            let mut depth = 0;
+            for (index, line) in lines.iter().enumerate().skip(begin + 1) {
+                if region_name(line).is_some() {
+                    depth += 1;
+                } else if comment(line) == Some(END_REGION) {
+                    if depth == 0 {
+                        return Ok((join(&lines[begin + 1..index]), begin + 2));
+                    }
+                    depth -= 1;
+                }
+            }
+            bail!("Region `{name}` is not closed by `{END_REGION}`");
+        }
+        Ok((join(&lines), 1))
+    }
+}
+
+// Name of the region started by the line, like `// region: name` or `<!-- region: name -->`
+fn region_name(line: &str) -> Option<&str> {
+    let name = comment(line)?.strip_prefix(REGION)?.trim();
+    (!name.is_empty()).then_some(name)
+}
+
+// Text of the comment if it takes the whole line
+fn comment(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let token = COMMENT_TOKENS
+        .iter()
+        .find(|token| line.starts_with(**token))?;
+    let text = line[token.len()..]
+        .trim_end_matches("-->")
+        .trim_end_matches("*/")
+        .trim();
+    Some(text)
+}
+
+fn join(lines: &[&str]) -> String {
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_source_range() {
+        let source = "use x;\n// region: main\nfn main() {\n    // region: body\n    run();\n    // endregion\n}\n// endregion\n";
+        let whole = SourceRange::default().extract(source).unwrap();
+        assert_eq!(whole, (source.to_string(), 1));
+
+        let lines = SourceRange::new(Some("3-5"), None).unwrap();
+        assert_eq!(
+            lines.extract(source).unwrap(),
+            (
+                "fn main() {\n    // region: body\n    run();\n".to_string(),
+                3
+            )
+        );
+        assert!(
+            SourceRange::new(Some("3-20"), None)
+                .unwrap()
+                .extract(source)
+                .is_err()
+        );
+
+        let main = SourceRange::new(None, Some("main")).unwrap();
+        assert_eq!(
+            main.extract(source).unwrap(),
+            (
+                "fn main() {\n    // region: body\n    run();\n    // endregion\n}\n".to_string(),
+                3
+            )
+        );
+        let body = SourceRange::new(None, Some("body")).unwrap();
+        assert_eq!(
+            body.extract(source).unwrap(),
+            ("    run();\n".to_string(), 5)
+        );
+        assert!(
+            SourceRange::new(None, Some("test"))
+                .unwrap()
+                .extract(source)
+                .is_err()
+        );
+        assert!(SourceRange::new(Some("1-2"), Some("main")).is_err());
+
+        // Only marker comments count, not code mentioning `region`
+        let source =
+            "let region = 1;\n# region: setup\nlet region: u8 = endregion();\n# endregion\n";
+        let setup = SourceRange::new(None, Some("setup")).unwrap();
+        assert_eq!(
+            setup.extract(source).unwrap(),
+            ("let region: u8 = endregion();\n".to_string(), 3)
+        );
+        assert!(
+            SourceRange::new(None, Some("= 1;"))
+                .unwrap()
+                .extract(source)
+                .is_err()
+        );
+    }
+}
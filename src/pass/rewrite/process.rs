@@ -0,0 +1,223 @@
+use crate::compile::compile_options;
+use crate::pass::pure::PurePass;
+use crate::pass::rewrite::*;
+use crate::config::rewrite::TagRewriteRule;
+use crate::util::fs::{prune_cache_dir, read_cache_entry, write_into_file};
+use crate::util::html::Attributes;
+use crate::util::str::ac_replace;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::process::{Command, Stdio};
+use std::result::Result::Ok;
+use std::thread;
+use std::time::{Duration, Instant};
+use typsite_macros::rewrite_pass;
+
+const PROCESS_CACHE_DIR: &str = "process";
+const DEFAULT_TIMEOUT: u64 = 30; // seconds
+// Outputs not used for this long are removed from the cache
+const PROCESS_CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// Runs `command` of the rule with the attributes and content of the tag as JSON on stdin,
+// its stdout is placed at `{output}`
+rewrite_pass![ProcessPass, id = "process", atom = true];
+impl TagRewritePass for ProcessPass {
+    fn init(&self, mut attrs: Attributes, pass: &mut PurePass) -> Result<HashMap<String, String>> {
+        let command = attrs
+            .take("command")
+            .context("ProcessPass: expect `command` attribute in the rule")?;
+        let timeout = attrs.take("timeout").ok();
+        let files = attrs.take("files").ok();
+        let content = attrs.take("content").unwrap_or_default();
+        let input = serde_json::json!({
+            "attributes": attrs.into_map(),
+            "content": content,
+        })
+        .to_string();
+        let mut attrs: HashMap<String, String> = [
+            ("command".to_string(), command),
+            ("input".to_string(), input),
+        ]
+        .into_iter()
+        .collect();
+        attrs.extend(timeout.map(|it| ("timeout".to_string(), it)));
+        attrs.extend(files.map(|it| ("files".to_string(), it)));
+        // Run it now, so that failures are reported on the article
+        Process::new(&attrs, pass.config.config_path())?
+            .output()
+            .with_context(|| format!("ProcessPass: `{}` failed", attrs["command"]))?;
+        Ok(attrs)
+    }
+
+    fn pure_start(
+        &self,
+        attrs: &HashMap<String, String>,
+        config: &TypsiteConfig,
+        body: &str,
+    ) -> Option<String> {
+        // Cached by `init`, unless the declared files changed since
+        let output = Process::new(attrs, config.config_path()).and_then(|it| it.output());
+        match output {
+            Ok(output) => Some(ac_replace(body, &[("{output}", output.as_str())])),
+            Err(err) => {
+                eprintln!("[WARN] ProcessPass: `{}` failed, {err:?}", attrs["command"]);
+                None
+            }
+        }
+    }
+}
+
+struct Process<'a> {
+    command: &'a str,
+    input: &'a str,
+    timeout: Duration,
+    files: Vec<Arc<Path>>,
+    // Commands run in the config dir
+    dir: &'a Path,
+}
+
+impl<'a> Process<'a> {
+    fn new(attrs: &'a HashMap<String, String>, dir: &'a Path) -> Result<Self> {
+        let timeout = match attrs.get("timeout") {
+            Some(timeout) => timeout
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid timeout `{timeout}`, expect seconds"))?,
+            None => DEFAULT_TIMEOUT,
+        };
+        // The same files as watched for the rule
+        let files = TagRewriteRule::declared_files(dir, attrs);
+        Ok(Self {
+            command: &attrs["command"],
+            input: &attrs["input"],
+            timeout: Duration::from_secs(timeout),
+            files,
+            dir,
+        })
+    }
+
+    // Keyed by the command, the input and the content of the declared files and program
+    fn hash(&self) -> Result<String> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.command.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(self.input.as_bytes());
+        for file in &self.files {
+            let content = std::fs::read(file)
+                .with_context(|| format!("Cannot read the declared file {file:?}"))?;
+            hasher.update(&[0]);
+            hasher.update(&content);
+        }
+        Ok(hasher.finalize().to_hex().to_string())
+    }
+
+    fn output(&self) -> Result<String> {
+        let dir = compile_options()?.cache_path.join(PROCESS_CACHE_DIR);
+        let cache = dir.join(self.hash()?).with_extension("html");
+        if let Some(output) = read_cache_entry(&cache) {
+            return Ok(output);
+        }
+        let output = self.run()?;
+        write_into_file(cache, &output, "process output")?;
+        prune_cache_dir(dir, PROCESS_CACHE_MAX_AGE);
+        Ok(output)
+    }
+
+    fn run(&self) -> Result<String> {
+        let mut args = self.command.split_whitespace();
+        let program = args.next().context("Empty command")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Cannot run `{}`", self.command))?;
+
+        // Pipes are drained in threads, so that a chatty command won't block
+        let mut stdin = child.stdin.take().unwrap();
+        let input = self.input.to_string();
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let stdout = drain(child.stdout.take().unwrap());
+        let stderr = drain(child.stderr.take().unwrap());
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                bail!("Timed out after {}s", self.timeout.as_secs());
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        let _ = writer.join();
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() {
+            bail!("Exited with {status}: {}", stderr.trim());
+        }
+        Ok(stdout)
+    }
+}
+
+fn drain(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = pipe.read_to_end(&mut buffer);
+        String::from_utf8_lossy(&buffer).to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process<'a>(attrs: &'a HashMap<String, String>, dir: &'a Path) -> Process<'a> {
+        Process::new(attrs, dir).unwrap()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_command() {
+        let dir = std::env::temp_dir();
+        let attrs: HashMap<String, String> = [
+            ("command".to_string(), "cat".to_string()),
+            ("input".to_string(), "{\"content\":\"x\"}".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(process(&attrs, &dir).run().unwrap(), "{\"content\":\"x\"}");
+
+        let mut attrs = attrs;
+        attrs.insert("command".to_string(), "false".to_string());
+        assert!(process(&attrs, &dir).run().is_err());
+
+        attrs.insert("command".to_string(), "sleep 5".to_string());
+        attrs.insert("timeout".to_string(), "0".to_string());
+        let err = process(&attrs, &dir).run().unwrap_err();
+        assert!(err.to_string().contains("Timed out"));
+    }
+
+    #[test]
+    fn hash_program_in_config_dir() {
+        let dir = std::env::temp_dir().join("typsite-process-hash-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let attrs: HashMap<String, String> = [
+            ("command".to_string(), "./render.sh --fast".to_string()),
+            ("input".to_string(), "{}".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        std::fs::write(dir.join("render.sh"), "echo a").unwrap();
+        let before = process(&attrs, &dir).hash().unwrap();
+        std::fs::write(dir.join("render.sh"), "echo b").unwrap();
+        assert_ne!(process(&attrs, &dir).hash().unwrap(), before);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
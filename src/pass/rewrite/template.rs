@@ -0,0 +1,73 @@
+use crate::pass::pure::PurePass;
+use crate::pass::rewrite::*;
+use crate::util::html::{Attributes, escape_xml};
+use crate::util::str::ac_replace;
+use std::collections::HashMap;
+use typsite_macros::rewrite_pass;
+
+// Substitutes every attribute into `{name}` of the rule,
+// the children are placed at `{body}`
+rewrite_pass![TemplatePass, id = "template", atom = false, pure = true];
+impl TagRewritePass for TemplatePass {
+    fn init(&self, attrs: Attributes, _: &mut PurePass) -> Result<HashMap<String, String>> {
+        Ok(attrs.into_map())
+    }
+
+    fn pure_start(
+        &self,
+        attrs: &HashMap<String, String>,
+        _: &TypsiteConfig,
+        body: &str,
+    ) -> Option<String> {
+        Some(template(attrs, body))
+    }
+
+    fn pure_end(
+        &self,
+        attrs: &HashMap<String, String>,
+        _: &TypsiteConfig,
+        tail: &str,
+    ) -> Option<String> {
+        Some(template(attrs, tail))
+    }
+}
+
+// Values are escaped, as they may be placed inside attributes of the rule
+fn template(attrs: &HashMap<String, String>, text: &str) -> String {
+    let substitutions = attrs
+        .iter()
+        .map(|(key, value)| (format!("{{{key}}}"), escape_xml(value)))
+        .collect::<Vec<_>>();
+    let replacements = substitutions
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect::<Vec<_>>();
+    ac_replace(text, &replacements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitute_attributes() {
+        let attrs = [
+            ("kind".to_string(), "warning".to_string()),
+            ("title".to_string(), "Careful".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            template(&attrs, "<aside class=\"{kind}\"><b>{title}</b>{unknown}"),
+            "<aside class=\"warning\"><b>Careful</b>{unknown}"
+        );
+
+        let attrs = [("title".to_string(), "\"><script>".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            template(&attrs, "<aside title=\"{title}\">"),
+            "<aside title=\"&quot;&gt;&lt;script&gt;\">"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::compile::error::{TypError, TypResult};
+use crate::compile::proj_options;
 use crate::config::TypsiteConfig;
-use crate::config::footer::{BACKLINKS_KEY, REFERENCES_KEY};
+use crate::config::footer::{BACKLINKS_KEY, CITATIONS_MARKER, FooterConfig, REFERENCES_KEY};
 use crate::config::schema::{BACKLINK_KEY, REFERENCE_KEY, Schema};
 use crate::ir::article::Article;
 use crate::ir::article::data::GlobalData;
@@ -13,6 +14,11 @@ use anyhow::Context;
 use html5gum::{Token, Tokenizer};
 use std::borrow::Cow;
 use std::fmt::Write;
+use std::path::PathBuf;
+
+const PDF_URL_REPLACEMENT: &str = "{pdf-url}";
+const PRINT_URL_REPLACEMENT: &str = "{print-url}";
+const LOCAL_GRAPH_URL_REPLACEMENT: &str = "{localgraph-url}";
 
 pub struct SchemaPass<'a, 'b, 'c, 'd> {
     config: &'a TypsiteConfig<'a>,
@@ -76,8 +82,10 @@ impl<'d, 'c: 'd, 'b: 'c, 'a: 'b> SchemaPass<'a, 'b, 'c, 'd> {
                 .backlinks
                 .iter()
                 .filter_map(|slug| self.global_data.article(slug))
-                .filter_map(|article| article.get_backlink())
+                .filter_map(|article| Some((article, article.get_backlink()?)))
+                .map(|(article, backlink)| self.with_citations(article, backlink))
                 .collect::<Vec<_>>();
+            let backlinks = backlinks.iter().collect::<Vec<_>>();
 
             let has_references = !references.is_empty();
             let has_backlinks = !backlinks.is_empty();
@@ -118,16 +126,92 @@ impl<'d, 'c: 'd, 'b: 'c, 'a: 'b> SchemaPass<'a, 'b, 'c, 'd> {
             } else {
                 String::default()
             };
-            footer.body = ac_replace(
-                footer_body,
-                &[(REFERENCES_KEY, &references), (BACKLINKS_KEY, &backlinks)],
+            // Custom relations, listed by the backlinks of the related articles
+            let relation_names = proj_options()
+                .map(|options| {
+                    options
+                        .relations
+                        .iter()
+                        .flat_map(|relation| relation.names())
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let relations = relation_names
+                .iter()
+                .map(|name| (FooterConfig::relation_key(name), name.as_str()))
+                .filter(|(key, _)| footer_body.contains(key.as_str()))
+                .map(|(key, name)| {
+                    let mut related = node.related(name).into_iter().collect::<Vec<_>>();
+                    related.sort();
+                    let items = related
+                        .into_iter()
+                        .filter_map(|slug| self.global_data.article(slug))
+                        .filter_map(|article| article.get_backlink())
+                        .fold(OutputHtml::empty(), |mut acc, x| {
+                            acc.body.push('\n');
+                            acc.body.push_str(&x.body.replace(CITATIONS_MARKER, ""));
+                            acc
+                        });
+                    let component = self.config.footer.relations.get(name);
+                    let html = match component {
+                        _ if items.body.is_empty() => String::default(),
+                        Some(component) => {
+                            footer.head.push(component.head.as_str());
+                            ac_replace(&component.body, &[(&key, &items.body)])
+                        }
+                        None => items.body,
+                    };
+                    (key, html)
+                })
+                .collect::<Vec<_>>();
+
+            let mut replacements = vec![
+                (REFERENCES_KEY, references.as_str()),
+                (BACKLINKS_KEY, backlinks.as_str()),
+            ];
+            replacements.extend(
+                relations
+                    .iter()
+                    .map(|(key, html)| (key.as_str(), html.as_str())),
             );
+            footer.body = ac_replace(footer_body, &replacements);
             footer
         } else {
             OutputHtml::empty()
         };
 
-        let body = metadata.inline(&self.schema.body);
+        // URLs of the files placed next to the page, empty if they are not generated
+        let path = self.article.path.as_ref();
+        let permalink = self.article.permalink();
+        let permalink = permalink.as_deref();
+        let url = |output: Option<anyhow::Result<PathBuf>>| {
+            output
+                .and_then(|output| output.ok())
+                .map(|output| format!("/{}", output.to_string_lossy().replace('\\', "/")))
+                .unwrap_or_default()
+        };
+        let options = proj_options().ok();
+        let pdf_url = url(self
+            .article
+            .get_meta_options()
+            .pdf
+            .then(|| self.config.output_pdf_path(path, permalink)));
+        let print_url = url(options
+            .as_ref()
+            .is_some_and(|options| options.print.enable)
+            .then(|| self.config.output_print_path(path, permalink)));
+        let local_graph_url = url(options
+            .as_ref()
+            .is_some_and(|options| options.local_graph.enable)
+            .then(|| self.config.output_local_graph_path(path, permalink)));
+        let body = metadata.inline_with(
+            &self.schema.body,
+            &[
+                (PDF_URL_REPLACEMENT, &pdf_url),
+                (PRINT_URL_REPLACEMENT, &print_url),
+            ],
+        );
         // Body
         let tokenizer = Tokenizer::new(&body);
         let mut err = TypError::new_schema(self.article.slug.clone(), self.schema.id.as_str());
@@ -179,8 +263,23 @@ impl<'d, 'c: 'd, 'b: 'c, 'a: 'b> SchemaPass<'a, 'b, 'c, 'd> {
                 Ok(Token::StartTag(tag)) if tag.name == b"footer" => {
                     err.ok(write_into!(self.body, "{}\n", footer.body))
                 }
+                Ok(Token::StartTag(tag)) if tag.name == b"citations" => {
+                    err.ok(write_into!(self.body, "{CITATIONS_MARKER}"))
+                }
+                Ok(Token::StartTag(tag)) if tag.name == b"search" => {
+                    let search = metadata.inline(self.config.search.body.as_str());
+                    err.ok(write_into!(self.body, "{search}\n"))
+                }
+                Ok(Token::StartTag(tag)) if tag.name == b"localgraph" => {
+                    let local_graph = metadata.inline_with(
+                        self.config.local_graph.body.as_str(),
+                        &[(LOCAL_GRAPH_URL_REPLACEMENT, &local_graph_url)],
+                    );
+                    err.ok(write_into!(self.body, "{local_graph}\n"))
+                }
                 Ok(Token::EndTag(tag)) => match tag.name.as_slice() {
-                    b"metadata" | b"sidebar" | b"content" | b"footer" => None,
+                    b"metadata" | b"sidebar" | b"content" | b"footer" | b"search"
+                    | b"localgraph" | b"citations" => None,
                     _ => err.ok(write_token(&mut self.body, &Token::EndTag(tag))),
                 },
                 Ok(token) => err.ok(write_token(&mut self.body, &token)),
@@ -196,4 +295,41 @@ impl<'d, 'c: 'd, 'b: 'c, 'a: 'b> SchemaPass<'a, 'b, 'c, 'd> {
         let html = OutputHtml::<'a>::new(head, self.body);
         Ok(html)
     }
+
+    // The backlink of the citing article, with where it cites this article
+    fn with_citations(&self, citing: &Article<'a>, backlink: &OutputHtml<'a>) -> OutputHtml<'a> {
+        if !backlink.body.contains(CITATIONS_MARKER) {
+            return OutputHtml::new(OutputHead::empty(), backlink.body.clone());
+        }
+        let component = self.config.footer.citation.body.as_str();
+        let citations = match self.global_data.metadata(citing.slug.as_str()) {
+            Some(metadata) if !component.is_empty() => {
+                let public_slug = metadata.contents.public_slug();
+                citing
+                    .get_citations()
+                    .iter()
+                    .filter(|citation| citation.slug == self.article.slug.as_str())
+                    .map(|citation| {
+                        let url = if citation.anchor.is_empty() {
+                            public_slug.clone()
+                        } else {
+                            format!("{public_slug}#{}", citation.anchor)
+                        };
+                        metadata.inline_with(
+                            component,
+                            &[
+                                ("{url}", &url),
+                                ("{anchor}", &citation.anchor),
+                                ("{context}", &citation.context),
+                            ],
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            _ => String::new(),
+        };
+        let body = backlink.body.replace(CITATIONS_MARKER, &citations);
+        OutputHtml::new(OutputHead::empty(), body)
+    }
 }
@@ -7,7 +7,9 @@ use std::iter::Peekable;
 
 use html5gum::{EndTag, HtmlString, StartTag, StringReader, Token};
 
+use crate::config::format_permalink;
 use crate::ir::embed::EmbedVariables;
+use crate::ir::metadata::content::{ALIASES_KEY, split_aliases};
 use crate::util::html::{Attributes, html_as_str};
 
 pub trait Label {
@@ -25,15 +27,21 @@ pub enum Event<T: Label> {
 pub enum HeadTag {
     Schema { schema: String },
     Unique,
+    Permalink { slug: String },
+    Alias { slug: String },
 }
 
 const SCHEMA_KEY: &str = "schema";
 const UNIQUE_KEY: &str = "unique";
+const PERMALINK_KEY: &str = "permalink";
+const ALIAS_KEY: &str = "alias";
 impl Label for HeadTag {
     fn name(&self) -> &'static str {
         match &self {
             HeadTag::Schema { .. } => SCHEMA_KEY,
             HeadTag::Unique => UNIQUE_KEY,
+            HeadTag::Permalink { .. } => PERMALINK_KEY,
+            HeadTag::Alias { .. } => ALIAS_KEY,
         }
     }
 }
@@ -55,6 +63,11 @@ pub enum BodyTag {
     MetaContentSet {
         key: String,
     },
+    EmbedVariable {
+        name: String,
+        kind: String,
+        default: Option<String>,
+    },
     // Content
     Section {
         heading_level: usize,
@@ -68,6 +81,8 @@ pub enum BodyTag {
         open: bool,
         variables: EmbedVariables,
         sidebar: String,
+        section: Option<String>,
+        template: Option<String>,
         heading_level: usize,
     },
     AnchorGoto {
@@ -83,6 +98,7 @@ const META_OPTION_KEY: &str = "metaoption";
 const META_CONTENT_KEY: &str = "metacontent";
 const HEADING_KEYS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
 const EMBED_KEY: &str = "embed";
+const EMBED_VARIABLE_KEY: &str = "embedvariable";
 const REWRITE_KEY: &str = "rewrite";
 const ANCHOR_DEF_KEY: &str = "anchordef";
 const ANCHOR_GOTO_KEY: &str = "anchorgoto";
@@ -96,6 +112,7 @@ impl Label for BodyTag {
             BodyTag::Section { heading_level } => HEADING_KEYS[*heading_level - 1],
             BodyTag::Rewrite { .. } => REWRITE_KEY,
             BodyTag::Embed { .. } => EMBED_KEY,
+            BodyTag::EmbedVariable { .. } => EMBED_VARIABLE_KEY,
             BodyTag::AnchorGoto { .. } => ANCHOR_GOTO_KEY,
             BodyTag::AnchorDef { .. } => ANCHOR_DEF_KEY,
         }
@@ -160,6 +177,22 @@ fn emit_head_next(
                     HeadTag::Schema { schema }
                 }
                 UNIQUE_KEY => HeadTag::Unique,
+                PERMALINK_KEY => {
+                    let attrs = Attributes::new(start_tag.attributes);
+                    let slug = attrs
+                        .get("slug")
+                        .context("Permalink: expect slug attribute")?
+                        .to_string();
+                    HeadTag::Permalink { slug }
+                }
+                ALIAS_KEY => {
+                    let attrs = Attributes::new(start_tag.attributes);
+                    let slug = attrs
+                        .get("slug")
+                        .context("Alias: expect slug attribute")?
+                        .to_string();
+                    HeadTag::Alias { slug }
+                }
                 _ => return Ok(Some(Event::Other(Token::StartTag(start_tag)))),
             };
             if !start_tag.self_closing {
@@ -172,7 +205,7 @@ fn emit_head_next(
             let name = String::from_utf8_lossy(&end_tag.name).to_string();
             let event = match name.as_str() {
                 "head" => Event::Eof,
-                SCHEMA_KEY => {
+                SCHEMA_KEY | PERMALINK_KEY | ALIAS_KEY => {
                     let backtrace = tokenizer.backtrace.pop();
                     Event::End(backtrace.context("Expect a start tag in the backtrace stack.")?)
                 }
@@ -218,6 +251,23 @@ fn emit_body_next(
                         .to_string();
                     BodyTag::MetaOption { key, value }
                 }
+                EMBED_VARIABLE_KEY => {
+                    let attrs = Attributes::new(start_tag.attributes);
+                    let name = attrs
+                        .get("name")
+                        .context("EmbedVariable: expect name attribute")?
+                        .to_string();
+                    let kind = attrs
+                        .get("type")
+                        .map(|it| it.to_string())
+                        .unwrap_or("string".to_string());
+                    let default = attrs.get("default").map(|it| it.to_string());
+                    BodyTag::EmbedVariable {
+                        name,
+                        kind,
+                        default,
+                    }
+                }
                 META_CONTENT_KEY => {
                     let attrs = Attributes::new(start_tag.attributes);
                     if let Some(key) = attrs.get("set") {
@@ -257,12 +307,17 @@ fn emit_body_next(
                                     .to_string();
                                 let open = attrs.take("open").map(|v| v == "true").unwrap_or(false);
                                 let sidebar = attrs.take("sidebar").unwrap_or("full".to_string());
+                                let section = attrs.take("section").ok();
+                                let template = attrs.take("template").ok();
+                                let _ = attrs.take("heading_level");
                                 let variables = attrs.into_variables();
                                 BodyTag::Embed {
                                     slug,
                                     open,
                                     variables,
                                     sidebar,
+                                    section,
+                                    template,
                                     heading_level,
                                 }
                             }
@@ -295,12 +350,16 @@ fn emit_body_next(
                         .take("sidebar")
                         .unwrap_or("full".to_string())
                         .to_string();
+                    let section = attrs.take("section").ok();
+                    let template = attrs.take("template").ok();
                     let variables = attrs.into_variables();
                     BodyTag::Embed {
                         slug,
                         open,
                         variables,
                         sidebar,
+                        section,
+                        template,
                         heading_level,
                     }
                 }
@@ -339,7 +398,7 @@ fn emit_body_next(
             match name.as_str() {
                 "body" => Ok(Some(Event::Eof)),
                 META_GRAPH_KEY | META_OPTION_KEY | META_CONTENT_KEY | REWRITE_KEY | EMBED_KEY
-                | ANCHOR_GOTO_KEY | ANCHOR_DEF_KEY | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                | EMBED_VARIABLE_KEY | ANCHOR_GOTO_KEY | ANCHOR_DEF_KEY | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
                     let backtrace = tokenizer.backtrace.pop();
                     Ok(Some(Event::End(
                         backtrace.context("Expect a start tag in the backtrace stack.")?,
@@ -469,3 +528,54 @@ fn emit_other_end(
     }
     Ok(Some(Event::Other(Token::EndTag(end_tag))))
 }
+
+// Collect the permalink & aliases of an article before passing it,
+// so that articles could refer to each other by permalinks
+pub fn scan_permalinks(src: &str) -> Result<(Option<String>, Vec<String>)> {
+    let mut permalink = None;
+    let mut aliases = Vec::new();
+    let mut meta_key: Option<String> = None;
+    let mut meta_content = String::new();
+    for token in html5gum::Tokenizer::new(src) {
+        match token.context("Error ocurred while parsing HTML")? {
+            Token::StartTag(start_tag) => {
+                let name = html_as_str(&start_tag.name);
+                let attrs = Attributes::new(start_tag.attributes);
+                match name.as_ref() {
+                    PERMALINK_KEY => {
+                        let slug = attrs.get("slug").context("Permalink: expect slug attribute")?;
+                        permalink = Some(slug.to_string());
+                    }
+                    ALIAS_KEY => {
+                        let slug = attrs.get("slug").context("Alias: expect slug attribute")?;
+                        aliases.push(slug.to_string());
+                    }
+                    META_CONTENT_KEY => {
+                        meta_key = attrs
+                            .get("set")
+                            .filter(|key| key == PERMALINK_KEY || key == ALIASES_KEY)
+                            .map(|key| key.to_string());
+                    }
+                    _ => {}
+                }
+            }
+            Token::String(text) if meta_key.is_some() => {
+                meta_content.push_str(&html_as_str(&text));
+            }
+            Token::EndTag(end_tag) if html_as_str(&end_tag.name) == META_CONTENT_KEY => {
+                let content = std::mem::take(&mut meta_content);
+                match meta_key.take().as_deref() {
+                    Some(PERMALINK_KEY) => permalink = Some(content),
+                    Some(_) => aliases.push(content),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    let permalink = permalink
+        .filter(|it| !it.trim().is_empty())
+        .map(|it| format_permalink(&it));
+    let aliases = aliases.iter().flat_map(|it| split_aliases(it)).collect();
+    Ok((permalink, aliases))
+}
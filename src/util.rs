@@ -1,5 +1,6 @@
 use crate::ir::article::sidebar::Pos;
 
+pub mod date;
 pub mod error;
 pub mod fs;
 pub mod html;
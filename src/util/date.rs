@@ -0,0 +1,124 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// (year, month, day, hour, minute, second) in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date(u32, u32, u32, u32, u32, u32);
+
+// The Unix epoch, for the undated
+impl Default for Date {
+    fn default() -> Self {
+        Date(1970, 1, 1, 0, 0, 0)
+    }
+}
+
+impl Date {
+    // Accepts `YYYY-MM-DD`, optionally followed by `THH:MM[:SS]` or ` HH:MM[:SS]`
+    pub fn parse(date: &str) -> Option<Date> {
+        let date = date.trim();
+        let (day, time) = date
+            .split_once(['T', ' '])
+            .map(|(day, time)| (day, Some(time)))
+            .unwrap_or((date, None));
+        let mut day = day.split('-').map(|it| it.parse::<u32>().ok());
+        let (year, month, day) = (day.next()??, day.next()??, day.next()??);
+        if year == 0 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        let mut time = time
+            .map(|time| time.trim_end_matches('Z'))
+            .filter(|time| !time.is_empty())
+            .into_iter()
+            .flat_map(|time| time.split(':'))
+            .map(|it| it.parse::<u32>().ok());
+        let hour = time.next().unwrap_or(Some(0))?;
+        let minute = time.next().unwrap_or(Some(0))?;
+        let second = time.next().unwrap_or(Some(0))?;
+        if time.next().is_some() || hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        Some(Date(year, month, day, hour, minute, second))
+    }
+
+    pub fn from_system_time(time: SystemTime) -> Option<Date> {
+        let secs = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let (days, secs) = (secs / 86400, secs % 86400);
+        // Howard Hinnant's civil_from_days
+        let z = days as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as u32;
+        let (hour, minute, second) = (secs / 3600, secs % 3600 / 60, secs % 60);
+        Some(Date(
+            year,
+            month,
+            day,
+            hour as u32,
+            minute as u32,
+            second as u32,
+        ))
+    }
+
+    pub fn rfc3339(&self) -> String {
+        let Date(year, month, day, hour, minute, second) = *self;
+        let (month, day) = (month.max(1), day.max(1));
+        format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+    }
+
+    pub fn rfc822(&self) -> String {
+        const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        let Date(year, month, day, hour, minute, second) = *self;
+        let month = month.clamp(1, 12);
+        // Sakamoto's method, signed as the year before year 0 is negative
+        const OFFSETS: [i64; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+        let y = year as i64 - if month < 3 { 1 } else { 0 };
+        let weekday = (y + y.div_euclid(4) - y.div_euclid(100)
+            + y.div_euclid(400)
+            + OFFSETS[month as usize - 1]
+            + day as i64)
+            .rem_euclid(7);
+        format!(
+            "{}, {day:02} {} {year:04} {hour:02}:{minute:02}:{second:02} +0000",
+            WEEKDAYS[weekday as usize],
+            MONTHS[month as usize - 1]
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format_date() {
+        let date = Date::parse("2024-10-20").unwrap();
+        assert_eq!(date.rfc3339(), "2024-10-20T00:00:00Z");
+        assert_eq!(date.rfc822(), "Sun, 20 Oct 2024 00:00:00 +0000");
+        let date = Date::parse("2025-01-01T08:30").unwrap();
+        assert_eq!(date.rfc822(), "Wed, 01 Jan 2025 08:30:00 +0000");
+        assert!(Date::parse("Unknown Date").is_none());
+        assert!(Date::parse("0000-01-01").is_none());
+        assert!(Date::parse("2025-01-01T99:99").is_none());
+        assert_eq!(
+            Date(0, 1, 1, 0, 0, 0).rfc822(),
+            "Sat, 01 Jan 0000 00:00:00 +0000"
+        );
+        assert_eq!(
+            Date::parse("1905-06-30").unwrap().rfc3339(),
+            "1905-06-30T00:00:00Z"
+        );
+        assert_eq!(Date::default().rfc3339(), "1970-01-01T00:00:00Z");
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1729382400 + 3661);
+        assert_eq!(
+            Date::from_system_time(time).unwrap().rfc3339(),
+            "2024-10-20T01:01:01Z"
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::util::error::TypsiteError;
 use anyhow::{Context, anyhow};
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 
 pub fn create_all_parent_dir<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
     let path = path.as_ref();
@@ -39,6 +40,33 @@ pub fn remove_dir_all<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
     Ok(())
 }
 
+// Read an entry of a cache dir, marking it as used for `prune_cache_dir`
+pub fn read_cache_entry<P: AsRef<Path>>(path: P) -> Option<String> {
+    let content = fs::read_to_string(path.as_ref()).ok()?;
+    if let Ok(file) = fs::File::options().append(true).open(path.as_ref()) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    Some(content)
+}
+
+// Remove the entries of a cache dir that are not used for `max_age`
+pub fn prune_cache_dir<P: AsRef<Path>>(dir: P, max_age: Duration) {
+    let Ok(entries) = fs::read_dir(dir.as_ref()) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let unused = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|elapsed| elapsed > max_age);
+        if unused {
+            remove_file_ignore(entry.path());
+        }
+    }
+}
+
 pub fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> anyhow::Result<()> {
     create_all_parent_dir(to.as_ref())?;
     std::fs::copy(from.as_ref(), to.as_ref())
@@ -10,7 +10,7 @@ use serde::de::{MapAccess, Visitor};
 use serde::ser::SerializeMap;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Write;
@@ -49,6 +49,25 @@ impl Attributes {
             .get(&HtmlString::from(key.as_bytes().to_vec()))
             .map(|v| String::from_utf8_lossy(&v.0))
     }
+    pub fn contains(&self, key: &str) -> bool {
+        self.attrs
+            .contains_key(&HtmlString::from(key.as_bytes().to_vec()))
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.attrs.insert(
+            HtmlString::from(key.as_bytes().to_vec()),
+            HtmlString::from(value.as_bytes().to_vec()),
+        );
+    }
+
+    pub fn into_map(self) -> HashMap<String, String> {
+        self.attrs
+            .into_iter()
+            .map(|(key, value)| (html_as_str(&key).to_string(), html_as_str(&value).to_string()))
+            .collect()
+    }
+
     pub fn into_variables(self) -> Vec<(String, String)> {
         self.attrs
             .into_iter()
@@ -335,6 +354,23 @@ impl<E: Elem> HtmlWithElem<E> {
     }
 }
 
+// Take the declaration tags named `name` out of the html, e.g. `<variable />` in the head of a component
+pub fn take_tags(html: &str, name: &str) -> Result<(String, Vec<Attributes>)> {
+    let mut rest = String::new();
+    let mut tags = Vec::new();
+    for token in Tokenizer::new(html) {
+        let token = token?;
+        match &token {
+            Token::StartTag(tag) if html_as_str(&tag.name) == name => {
+                tags.push(Attributes::new(tag.attributes.clone()));
+            }
+            Token::EndTag(tag) if html_as_str(&tag.name) == name => {}
+            _ => write_token(&mut rest, &token)?,
+        }
+    }
+    Ok((rest, tags))
+}
+
 #[macro_export]
 macro_rules! write_into {
     ($dst:expr, $($arg:tt)*) => {
@@ -484,6 +520,94 @@ pub fn html(head: &str, body: &str) -> String {
     )
 }
 
+pub fn escape_xml(text: &str) -> String {
+    ac_replace(
+        text,
+        &[
+            ("&", "&amp;"),
+            ("<", "&lt;"),
+            (">", "&gt;"),
+            ("\"", "&quot;"),
+            ("'", "&apos;"),
+        ],
+    )
+}
+
+// Plain text of an HTML fragment, for titles and summaries
+pub fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for char in html.chars() {
+        match char {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(char),
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+// (anchor, title) of sidebar items, which link to their sections by `#anchor`
+pub fn sidebar_links(sidebar: &str) -> Vec<(String, String)> {
+    let mut headings = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for token in Tokenizer::new(sidebar).flatten() {
+        match token {
+            Token::StartTag(tag) if tag.name.as_slice() == b"a" => {
+                let href = tag
+                    .attributes
+                    .get(b"href".as_slice())
+                    .map(|href| html_as_str(href).to_string());
+                if let Some(anchor) = href.as_deref().and_then(|it| it.strip_prefix('#')) {
+                    current = Some((anchor.to_string(), String::new()));
+                }
+            }
+            Token::String(string) => {
+                if let Some((_, title)) = current.as_mut() {
+                    title.push_str(&html_as_str(&string));
+                }
+            }
+            Token::EndTag(tag) if tag.name.as_slice() == b"a" => {
+                if let Some((anchor, title)) = current.take() {
+                    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if !title.is_empty() {
+                        headings.push((anchor, title));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+// (output path, page slug, fragment) of a link to a local page, e.g. `/a/b.html#c`,
+// the absolute URLs under `base_url` are accepted as well.
+pub fn local_page_link<'l>(
+    link: &'l str,
+    base_url: &str,
+) -> Option<(&'l str, &'l str, Option<&'l str>)> {
+    let link = link
+        .strip_prefix(base_url)
+        .filter(|_| !base_url.is_empty())
+        .unwrap_or(link);
+    if !link.starts_with('/') || link.starts_with("//") {
+        return None;
+    }
+    let (path, fragment) = link
+        .split_once('#')
+        .map(|(path, fragment)| (path, Some(fragment)))
+        .unwrap_or((link, None));
+    let slug = path.strip_suffix(".html").unwrap_or(path);
+    let slug = if slug.len() > 1 {
+        slug.trim_end_matches('/')
+    } else {
+        slug
+    };
+    Some((path, slug, fragment))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
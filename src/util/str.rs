@@ -18,6 +18,75 @@ pub fn ac_replace(text: &str, replacements: &[(&str, &str)]) -> String {
     ac_replace_map(text, replacements.iter().cloned().unzip())
 }
 
+const MAX_SUGGESTIONS: usize = 3;
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(current)
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+fn last_segment(s: &str) -> &str {
+    s.trim_end_matches('/').rsplit('/').next().unwrap_or(s)
+}
+
+// Find the closest candidates of target, ranked by edit distance,
+// the last path segment is also matched, so that typos in deep paths could be found
+pub fn suggest<'s, I>(target: &str, candidates: I) -> Vec<&'s str>
+where
+    I: IntoIterator<Item = &'s str>,
+{
+    let target_segment = last_segment(target);
+    let full_limit = (target.chars().count() / 3).max(2);
+    let segment_limit = (target_segment.chars().count() / 3).max(1);
+    let mut ranked = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != target)
+        .filter_map(|candidate| {
+            let full = edit_distance(target, candidate);
+            let segment = edit_distance(target_segment, last_segment(candidate));
+            if full <= full_limit || segment <= segment_limit {
+                Some(((full.min(segment), full), candidate))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    ranked.sort();
+    ranked.dedup_by_key(|(_, candidate)| *candidate);
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+// ", did you mean: a, b?" or empty if no candidate is close enough
+pub fn did_you_mean<'s, I>(target: &str, candidates: I) -> String
+where
+    I: IntoIterator<Item = &'s str>,
+{
+    let suggestions = suggest(target, candidates);
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(", did you mean: {}?", suggestions.join(", "))
+    }
+}
+
 pub trait Elem {
     fn parse_not_plain(token: &str) -> Self;
     fn plain(plain: String) -> Self;
@@ -215,6 +284,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_suggest() {
+        let candidates = ["/probability/foo", "/probability/bar", "/algebra/group", "/index"];
+        assert_eq!(
+            suggest("/prob/foo", candidates.iter().copied()),
+            vec!["/probability/foo"]
+        );
+        assert_eq!(suggest("/algebra/gruop", candidates.iter().copied()), vec!["/algebra/group"]);
+        assert!(suggest("/topology", candidates.iter().copied()).is_empty());
+        assert_eq!(did_you_mean("/inde", candidates.iter().copied()), ", did you mean: /index?");
+    }
+
     #[test]
     fn test_edge_cases() {
         let empty = ElemTokenizer::from::<SidebarElem>("").collect();